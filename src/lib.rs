@@ -1,8 +0,0 @@
-#[macro_use]
-extern crate lazy_static;
-#[macro_use]
-extern crate aoc_runner_derive;
-
-mod y2020;
-
-aoc_lib! {year = 2020}
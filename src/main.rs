@@ -0,0 +1,171 @@
+use aoc_runner::{ArcStr, Runner};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::process;
+use std::time::Instant;
+
+type Factory = fn(ArcStr) -> Result<Box<dyn Runner>, Box<dyn Error>>;
+
+/// (day, part, factory) for every registered solution in a year.
+type Solutions = &'static [(u8, u8, Factory)];
+
+macro_rules! solutions {
+    ($krate:ident { $($day:literal, $part:literal => $tr:ident :: $func:ident;)* }) => {
+        &[$(($day, $part, <$krate::Factory as $krate::$tr>::$func as Factory),)*]
+    };
+}
+
+const Y2019: Solutions = solutions!(aoc_2019 {
+    1, 1 => Day1Part1::day1_part1;
+    1, 2 => Day1Part2::day1_part2;
+    2, 1 => Day2Part1::day2_part1;
+    2, 2 => Day2Part2::day2_part2;
+    5, 1 => Day5Part1::day5_part1;
+    5, 2 => Day5Part2::day5_part2;
+    7, 1 => Day7Part1::day7_part1;
+    7, 2 => Day7Part2::day7_part2;
+    9, 1 => Day9Part1::day9_part1;
+    9, 2 => Day9Part2::day9_part2;
+    11, 1 => Day11Part1::day11_part1;
+    11, 2 => Day11Part2::day11_part2;
+    13, 1 => Day13Part1::day13_part1;
+    13, 2 => Day13Part2::day13_part2;
+});
+
+const Y2020: Solutions = solutions!(aoc_2020 {
+    1, 1 => Day1Part1::day1_part1;
+    1, 2 => Day1Part2::day1_part2;
+    2, 1 => Day2Part1::day2_part1;
+    2, 2 => Day2Part2::day2_part2;
+    3, 1 => Day3Part1::day3_part1;
+    3, 2 => Day3Part2::day3_part2;
+    4, 1 => Day4Part1::day4_part1;
+    4, 2 => Day4Part2::day4_part2;
+    5, 1 => Day5Part1::day5_part1;
+    5, 2 => Day5Part2::day5_part2;
+    6, 1 => Day6Part1::day6_part1;
+    6, 2 => Day6Part2::day6_part2;
+    7, 1 => Day7Part1::day7_part1;
+    7, 2 => Day7Part2::day7_part2;
+    8, 1 => Day8Part1::day8_part1;
+    8, 2 => Day8Part2::day8_part2;
+    9, 1 => Day9Part1::day9_part1;
+    9, 2 => Day9Part2::day9_part2;
+    10, 1 => Day10Part1::day10_part1;
+    10, 2 => Day10Part2::day10_part2;
+    11, 1 => Day11Part1::day11_part1;
+    11, 2 => Day11Part2::day11_part2;
+    12, 1 => Day12Part1::day12_part1;
+    12, 2 => Day12Part2::day12_part2;
+    13, 1 => Day13Part1::day13_part1;
+    13, 2 => Day13Part2::day13_part2;
+    14, 1 => Day14Part1::day14_part1;
+    14, 2 => Day14Part2::day14_part2;
+    15, 1 => Day15Part1::day15_part1;
+    15, 2 => Day15Part2::day15_part2;
+    16, 1 => Day16Part1::day16_part1;
+    16, 2 => Day16Part2::day16_part2;
+    17, 1 => Day17Part1::day17_part1;
+    17, 2 => Day17Part2::day17_part2;
+    18, 1 => Day18Part1::day18_part1;
+    18, 2 => Day18Part2::day18_part2;
+    19, 1 => Day19Part1::day19_part1;
+    19, 2 => Day19Part2::day19_part2;
+    20, 1 => Day20Part1::day20_part1;
+    20, 2 => Day20Part2::day20_part2;
+    21, 1 => Day21Part1::day21_part1;
+    21, 2 => Day21Part2::day21_part2;
+    22, 1 => Day22Part1::day22_part1;
+    22, 2 => Day22Part2::day22_part2;
+    24, 1 => Day24Part1::day24_part1;
+    24, 2 => Day24Part2::day24_part2;
+    25, 1 => Day25Part1::day25_part1;
+});
+
+fn solutions_for(year: u32) -> Option<Solutions> {
+    match year {
+        2019 => Some(Y2019),
+        2020 => Some(Y2020),
+        _ => None,
+    }
+}
+
+fn run(year: u32, day: u8, part: u8, factory: Factory) {
+    let path = format!("input/{}/day{}.txt", year, day);
+    let input = match fs::read_to_string(&path) {
+        Ok(input) => ArcStr::from(&input),
+        Err(e) => {
+            eprintln!(
+                "Day {} - Part {}: FAILED reading {}: {}\n",
+                day, part, path, e
+            );
+            return;
+        }
+    };
+
+    let start_time = Instant::now();
+
+    match factory(input) {
+        Ok(runner) => {
+            let inter_time = Instant::now();
+
+            match runner.try_run() {
+                Ok(result) => {
+                    let final_time = Instant::now();
+                    println!(
+                        "Day {} - Part {}: {}\n\tgenerator: {:?},\n\trunner: {:?}\n",
+                        day,
+                        part,
+                        result,
+                        inter_time - start_time,
+                        final_time - inter_time
+                    );
+                }
+                Err(e) => eprintln!(
+                    "Day {} - Part {}: FAILED while running:\n{:#?}\n",
+                    day, part, e
+                ),
+            }
+        }
+        Err(e) => eprintln!(
+            "Day {} - Part {}: FAILED while generating:\n{:#?}\n",
+            day, part, e
+        ),
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: Option<String>, name: &str) -> Option<T> {
+    arg.map(|a| {
+        a.trim_start_matches(name).parse().unwrap_or_else(|_| {
+            eprintln!("Invalid {}: {}", name, a);
+            process::exit(1);
+        })
+    })
+}
+
+/// Usage: `aoc-rust <year> [day] [part]`, e.g. `cargo run --release -- 2019 day5 part2`.
+/// Inputs are read from `input/<year>/day<day>.txt`.
+fn main() {
+    let mut args = env::args().skip(1);
+
+    let year: u32 = parse_arg(args.next(), "year").unwrap_or_else(|| {
+        eprintln!("Usage: aoc-rust <year> [day] [part]");
+        process::exit(1);
+    });
+    let day: Option<u8> = parse_arg(args.next(), "day");
+    let part: Option<u8> = parse_arg(args.next(), "part");
+
+    let solutions = solutions_for(year).unwrap_or_else(|| {
+        eprintln!("No solutions registered for {}", year);
+        process::exit(1);
+    });
+
+    println!("Advent of code {}", year);
+
+    for &(d, p, factory) in solutions {
+        if day.map_or(true, |day| day == d) && part.map_or(true, |part| part == p) {
+            run(year, d, p, factory);
+        }
+    }
+}
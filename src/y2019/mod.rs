@@ -1,7 +0,0 @@
-mod day01;
-mod day02;
-mod day05;
-mod day09;
-mod day11;
-
-mod intcode;
@@ -0,0 +1,80 @@
+//! Times the interpreter plain, with the decoded-instruction cache, and
+//! with compiled instructions. Loop-heavy programs gain the most; day 2's
+//! program runs each instruction once, so there both are pure overhead.
+//! Run with `cargo bench -p aoc-2019`.
+
+use aoc_2019::intcode::loader::load_file;
+use aoc_2019::intcode::{IntCodeInterpreter, MemoryData};
+use std::time::{Duration, Instant};
+
+fn time<F: FnMut()>(runs: u32, mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..runs {
+        f();
+    }
+    start.elapsed() / runs
+}
+
+#[derive(Clone, Copy)]
+enum Backend {
+    Plain,
+    Cached,
+    Compiled,
+}
+
+impl Backend {
+    fn apply(self, interp: &mut IntCodeInterpreter) {
+        match self {
+            Backend::Plain => {}
+            Backend::Cached => interp.set_decode_cache(true),
+            Backend::Compiled => interp.set_compiled(true),
+        }
+    }
+}
+
+fn compare(name: &str, runs: u32, run: impl Fn(Backend)) {
+    let plain = time(runs, || run(Backend::Plain));
+    let cached = time(runs, || run(Backend::Cached));
+    let compiled = time(runs, || run(Backend::Compiled));
+    let speedup = |d: Duration| plain.as_secs_f64() / d.as_secs_f64();
+    println!(
+        "{:<24} {:>10.3?} plain {:>10.3?} cached {:>6.2}x {:>10.3?} compiled {:>6.2}x",
+        name,
+        plain,
+        cached,
+        speedup(cached),
+        compiled,
+        speedup(compiled)
+    );
+}
+
+fn input(day: u32) -> MemoryData {
+    let path = format!(
+        "{}/../input/2019/day{}.txt",
+        env!("CARGO_MANIFEST_DIR"),
+        day
+    );
+    load_file(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+fn main() {
+    let boost = input(9);
+    compare("day 9 part 2 (BOOST)", 10, |backend| {
+        let mut interp = IntCodeInterpreter::new(&boost);
+        backend.apply(&mut interp);
+        assert_eq!(interp.run_to_completion(vec![2]).unwrap().len(), 1);
+    });
+
+    let gravity = input(2);
+    compare("day 2 part 2 (99x99)", 10, |backend| {
+        for noun in 0..=99 {
+            for verb in 0..=99 {
+                let mut interp = IntCodeInterpreter::new(&gravity);
+                interp.memory[1] = noun;
+                interp.memory[2] = verb;
+                backend.apply(&mut interp);
+                let _ = interp.run();
+            }
+        }
+    });
+}
@@ -10,7 +10,7 @@ pub fn mass_parser(input: &str) -> Vec<i64> {
 }
 
 #[aoc(day1, part1)]
-pub fn day1_part1(masses: &Vec<i64>) -> i64 {
+pub fn day1_part1(masses: &[i64]) -> i64 {
     masses
         .iter()
         .fold(0, |acc, m| acc + ((*m as f64 / 3.).floor() - 2.) as i64)
@@ -25,7 +25,7 @@ fn fuel_for_mass(mass: i64) -> i64 {
 }
 
 #[aoc(day1, part2)]
-pub fn day1_part2(masses: &Vec<i64>) -> i64 {
+pub fn day1_part2(masses: &[i64]) -> i64 {
     masses
         .iter()
         .fold(0, |acc, mass| acc + fuel_for_mass(*mass))
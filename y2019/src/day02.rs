@@ -0,0 +1,68 @@
+use crate::intcode::loader::{parse_program, LoadError};
+use crate::intcode::search::{Goal, Search, Unknown};
+use crate::intcode::{InstructionByte, IntCodeInterpreter, IntcodeError};
+use std::vec::Vec;
+
+#[aoc_generator(day2)]
+pub fn parser(input: &str) -> Result<Vec<InstructionByte>, LoadError> {
+    parse_program(input)
+}
+
+#[aoc(day2, part1)]
+pub fn day2_part1(instructions: &[InstructionByte]) -> Result<InstructionByte, IntcodeError> {
+    let mut memory = instructions.to_vec();
+    memory[1] = 12;
+    memory[2] = 2;
+
+    let mut interp = IntCodeInterpreter::new(&memory);
+    interp.run()?;
+    Ok(interp.memory[0])
+}
+
+#[aoc(day2, part2)]
+pub fn day1_part2(instructions: &Vec<InstructionByte>) -> Option<InstructionByte> {
+    let unknown = |address| Unknown {
+        address,
+        values: 0..=99,
+    };
+    let search = Search::new(instructions, vec![unknown(1), unknown(2)], Goal::Memory(0));
+    let values = search.solve(19690720)?;
+    Some(100 * values[0] + values[1])
+}
+
+#[test]
+pub fn tests() {
+    let inst: [InstructionByte; 12] = [1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50];
+    let mut prog = IntCodeInterpreter::new(&inst.to_vec());
+    prog.run().unwrap();
+    assert_eq!(3500, prog.memory[0]);
+}
+
+#[test]
+pub fn test2() {
+    pub fn run(input: &[InstructionByte]) -> Vec<InstructionByte> {
+        let mem = input.to_vec();
+        let mut prog = IntCodeInterpreter::new(&mem);
+        prog.run().unwrap();
+        prog.memory.to_vec()
+    }
+
+    assert_eq!(run(&[1, 0, 0, 0, 99]), [2, 0, 0, 0, 99]);
+    assert_eq!(run(&[2, 3, 0, 3, 99]), [2, 3, 0, 6, 99]);
+    assert_eq!(run(&[2, 4, 4, 5, 99, 0]), [2, 4, 4, 5, 99, 9801]);
+    assert_eq!(
+        run(&[1, 1, 1, 4, 99, 5, 6, 0, 99]),
+        [30, 1, 1, 4, 2, 5, 6, 0, 99]
+    );
+}
+
+#[test]
+pub fn part1_test() {
+    // ADD [12], [2] -> [3]; MUL [3], [13] -> [0]; HALT, where [1] and [2]
+    // become 12 and 2, so the answer only appears once the MUL has run
+    let program = vec![1, 0, 0, 3, 2, 3, 13, 0, 99, 0, 0, 0, 5, 7];
+    assert_eq!(day2_part1(&program), Ok(49));
+
+    let input = parser(include_str!("../../input/2019/day2.txt")).unwrap();
+    assert_eq!(day2_part1(&input), Ok(2842648));
+}
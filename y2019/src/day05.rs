@@ -0,0 +1,80 @@
+use crate::intcode::loader::{parse_program, LoadError};
+use crate::intcode::{InstructionByte, IntCodeInterpreter, IntcodeError};
+use std::vec::Vec;
+
+#[aoc_generator(day5)]
+pub fn parser(input: &str) -> Result<Vec<InstructionByte>, LoadError> {
+    parse_program(input)
+}
+
+fn run(
+    instructions: &Vec<InstructionByte>,
+    input: InstructionByte,
+) -> Result<InstructionByte, IntcodeError> {
+    let mut interp = IntCodeInterpreter::new(instructions);
+    // Earlier outputs are test results, and the diagnostic code comes last
+    interp.last_output(vec![input])
+}
+
+#[aoc(day5, part1)]
+pub fn day5_part1(instructions: &Vec<InstructionByte>) -> Result<InstructionByte, IntcodeError> {
+    run(instructions, 1)
+}
+
+#[aoc(day5, part2)]
+pub fn day5_part2(instructions: &Vec<InstructionByte>) -> Result<InstructionByte, IntcodeError> {
+    run(instructions, 5)
+}
+
+#[test]
+pub fn tests() {
+    let inst: [InstructionByte; 5] = [1002, 4, 3, 4, 33];
+    let mut prog = IntCodeInterpreter::new(&inst.to_vec());
+    prog.run().unwrap();
+    assert_eq!(99, prog.memory[4]);
+}
+
+#[test]
+pub fn test2() {
+    let mut prog = IntCodeInterpreter::new(&[1101, 100, -1, 4, 0].to_vec());
+    prog.run().unwrap();
+    assert_eq!(99, prog.memory[4]);
+}
+
+#[test]
+pub fn echo_test() {
+    use crate::intcode::Action;
+
+    let mut prog = IntCodeInterpreter::new(&[3, 0, 4, 0, 99].to_vec());
+    prog.write_input(42);
+    let out = prog.run().unwrap();
+    match out {
+        Action::Output(o) => {
+            assert_eq!(42, o);
+        }
+        _ => unimplemented!("Unexpected action result"),
+    }
+}
+
+#[test]
+pub fn need_input_test() {
+    use crate::intcode::Action;
+
+    let mut prog = IntCodeInterpreter::new(&[3, 0, 4, 0, 99].to_vec());
+    match prog.run().unwrap() {
+        Action::NeedInput => {}
+        _ => unimplemented!("Unexpected action result"),
+    }
+
+    prog.write_input(7);
+    match prog.run().unwrap() {
+        Action::Output(o) => {
+            assert_eq!(7, o);
+        }
+        _ => unimplemented!("Unexpected action result"),
+    }
+    match prog.run().unwrap() {
+        Action::Halt => {}
+        _ => unimplemented!("Unexpected action result"),
+    }
+}
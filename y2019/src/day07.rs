@@ -0,0 +1,146 @@
+use crate::intcode::cluster::{Cluster, ClusterError, ClusterEvent, Topology};
+use crate::intcode::loader::{parse_program, LoadError};
+use crate::intcode::{InstructionByte, IntCodeInterpreter};
+use itertools::Itertools;
+use std::error::Error;
+use std::fmt;
+use std::vec::Vec;
+
+const AMPLIFIERS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmplifierError {
+    Cluster(ClusterError),
+    /// The last amplifier never output a signal, or the amplifiers ended up
+    /// all waiting on each other.
+    NoSignal,
+}
+
+impl fmt::Display for AmplifierError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AmplifierError::Cluster(e) => write!(f, "{}", e),
+            AmplifierError::NoSignal => write!(f, "no signal reached the thrusters"),
+        }
+    }
+}
+
+impl Error for AmplifierError {}
+
+impl From<ClusterError> for AmplifierError {
+    fn from(e: ClusterError) -> Self {
+        AmplifierError::Cluster(e)
+    }
+}
+
+#[aoc_generator(day7)]
+pub fn parser(input: &str) -> Result<Vec<InstructionByte>, LoadError> {
+    parse_program(input)
+}
+
+/// Runs one amplifier per phase setting, wired up by `topology`, with each
+/// reading its phase and then the signals it's sent. Amplifier 0 is sent 0
+/// to start things off, and the last amplifier's final output goes to the
+/// thrusters.
+fn thruster_signal(
+    instructions: &Vec<InstructionByte>,
+    phases: &[InstructionByte],
+    topology: Topology,
+) -> Result<InstructionByte, AmplifierError> {
+    let amplifiers = phases
+        .iter()
+        .map(|phase| {
+            let mut amplifier = IntCodeInterpreter::new(instructions);
+            amplifier.write_input(*phase);
+            amplifier
+        })
+        .collect();
+
+    let mut cluster = Cluster::new(amplifiers, topology)?;
+    cluster.send(0, 0);
+    match cluster.run()? {
+        ClusterEvent::AllHalted => cluster
+            .last_output(phases.len() - 1)
+            .ok_or(AmplifierError::NoSignal),
+        _ => Err(AmplifierError::NoSignal),
+    }
+}
+
+/// The highest thruster signal from any ordering of `phases`.
+fn best_signal(
+    instructions: &Vec<InstructionByte>,
+    phases: std::ops::Range<InstructionByte>,
+    topology: Topology,
+) -> Result<InstructionByte, AmplifierError> {
+    let mut best = None;
+    for order in phases.permutations(AMPLIFIERS) {
+        let signal = thruster_signal(instructions, &order, topology.clone())?;
+        best = best.max(Some(signal));
+    }
+    best.ok_or(AmplifierError::NoSignal)
+}
+
+/// Each amplifier feeds the next, and the last feeds only the thrusters.
+fn chain() -> Topology {
+    let mut edges: Vec<Vec<usize>> = (1..AMPLIFIERS).map(|to| vec![to]).collect();
+    edges.push(Vec::new());
+    Topology::Custom(edges)
+}
+
+#[aoc(day7, part1)]
+pub fn day7_part1(instructions: &Vec<InstructionByte>) -> Result<InstructionByte, AmplifierError> {
+    best_signal(instructions, 0..5, chain())
+}
+
+// The last amplifier also feeds back into the first, and they all keep
+// going until they halt
+#[aoc(day7, part2)]
+pub fn day7_part2(instructions: &Vec<InstructionByte>) -> Result<InstructionByte, AmplifierError> {
+    best_signal(instructions, 5..10, Topology::Ring)
+}
+
+#[test]
+pub fn part1_test() {
+    let program = vec![
+        3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+    ];
+    assert_eq!(
+        thruster_signal(&program, &[4, 3, 2, 1, 0], chain()),
+        Ok(43210)
+    );
+    assert_eq!(day7_part1(&program), Ok(43210));
+
+    let program = vec![
+        3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23, 99,
+        0, 0,
+    ];
+    assert_eq!(day7_part1(&program), Ok(54321));
+
+    let program = vec![
+        3, 31, 3, 32, 1002, 32, 10, 32, 1001, 31, -2, 31, 1007, 31, 0, 33, 1002, 33, 7, 33, 1, 33,
+        31, 31, 1, 32, 31, 31, 4, 31, 99, 0, 0, 0,
+    ];
+    assert_eq!(day7_part1(&program), Ok(65210));
+}
+
+#[test]
+pub fn part2_test() {
+    let program = vec![
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+    assert_eq!(day7_part2(&program), Ok(139629729));
+
+    let program = vec![
+        3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54, -5,
+        54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4, 53,
+        1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+    ];
+    assert_eq!(day7_part2(&program), Ok(18216));
+
+    // A program that never outputs gives no signal
+    assert_eq!(
+        thruster_signal(&vec![3, 0, 99], &[5, 6, 7, 8, 9], Topology::Ring),
+        Err(AmplifierError::NoSignal)
+    );
+}
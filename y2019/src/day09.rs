@@ -0,0 +1,55 @@
+use crate::intcode::loader::{parse_program, LoadError};
+use crate::intcode::{InstructionByte, IntCodeInterpreter, IntcodeError};
+use std::vec::Vec;
+
+#[aoc_generator(day9)]
+pub fn parser(input: &str) -> Result<Vec<InstructionByte>, LoadError> {
+    parse_program(input)
+}
+
+fn run(
+    instructions: &Vec<InstructionByte>,
+    input: InstructionByte,
+) -> Result<InstructionByte, IntcodeError> {
+    let mut interp = IntCodeInterpreter::new(instructions);
+    // BOOST spends its time in a few tight loops, and doesn't modify itself
+    interp.set_compiled(true);
+    interp.last_output(vec![input])
+}
+
+#[aoc(day9, part1)]
+pub fn part1(instructions: &Vec<InstructionByte>) -> Result<InstructionByte, IntcodeError> {
+    run(instructions, 1)
+}
+
+#[aoc(day9, part2)]
+pub fn part2(instructions: &Vec<InstructionByte>) -> Result<InstructionByte, IntcodeError> {
+    run(instructions, 2)
+}
+
+#[test]
+pub fn quine_test() {
+    use crate::intcode::Action;
+
+    let original_program = vec![
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ];
+
+    let mut prog = IntCodeInterpreter::new(&original_program);
+    let mut output = Vec::<InstructionByte>::new();
+
+    loop {
+        let out = prog.run().unwrap();
+        match out {
+            Action::Output(o) => {
+                output.push(o);
+            }
+            Action::Halt => {
+                break;
+            }
+            _ => unimplemented!("Unexpected action result"),
+        }
+    }
+
+    assert_eq!(output, original_program);
+}
@@ -0,0 +1,58 @@
+use crate::intcode::grid::Pos;
+use crate::intcode::loader::{parse_program, LoadError};
+use crate::intcode::robot::{run_robot, PaintingProtocol, Robot, RobotError, World};
+use crate::intcode::{InstructionByte, IntCodeInterpreter};
+use std::vec::Vec;
+
+#[aoc_generator(day11)]
+pub fn parser(input: &str) -> Result<Vec<InstructionByte>, LoadError> {
+    parse_program(input)
+}
+
+fn paint(instructions: &Vec<InstructionByte>, mut world: World) -> Result<World, RobotError> {
+    let mut interp = IntCodeInterpreter::new(instructions);
+    run_robot(
+        &mut interp,
+        &mut PaintingProtocol,
+        &mut Robot::default(),
+        &mut world,
+    )?;
+
+    Ok(world)
+}
+
+#[aoc(day11, part1)]
+pub fn day11_part1(instructions: &Vec<InstructionByte>) -> Result<usize, RobotError> {
+    let world = paint(instructions, World::default())?;
+    Ok(world.painted.len())
+}
+
+#[aoc(day11, part2)]
+pub fn day11_part2(instructions: &Vec<InstructionByte>) -> Result<String, RobotError> {
+    let mut world = World::default();
+    world.painted.set(Pos::default(), 1);
+
+    let world = paint(instructions, world)?;
+    let image = world
+        .painted
+        .render(|color| if color == Some(&1) { '*' } else { ' ' });
+
+    Ok(format!("\n{}", image))
+}
+
+#[test]
+pub fn pos_hash() {
+    use std::collections::HashMap;
+
+    let mut hash = HashMap::<Pos, &str>::new();
+
+    let k1 = Pos { row: 1, col: 2 };
+    let k2 = Pos { row: 2, col: 1 };
+
+    hash.insert(k1, "k1");
+    hash.insert(k2, "k2");
+
+    let new_k1 = Pos { row: 1, col: 2 };
+    let v1 = *hash.get(&new_k1).unwrap();
+    assert_eq!(v1, "k1");
+}
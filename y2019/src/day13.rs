@@ -0,0 +1,27 @@
+use crate::intcode::arcade::{play, track_ball, ArcadeError, Screen, Tile};
+use crate::intcode::loader::{parse_program, LoadError};
+use crate::intcode::{InstructionByte, IntCodeInterpreter};
+use std::vec::Vec;
+
+#[aoc_generator(day13)]
+pub fn parser(input: &str) -> Result<Vec<InstructionByte>, LoadError> {
+    parse_program(input)
+}
+
+#[aoc(day13, part1)]
+pub fn day13_part1(instructions: &Vec<InstructionByte>) -> Result<usize, ArcadeError> {
+    let mut screen = Screen::default();
+    let mut interp = IntCodeInterpreter::new(instructions);
+    play(&mut interp, &mut screen, track_ball, |_| {})?;
+    Ok(screen.count(Tile::Block))
+}
+
+#[aoc(day13, part2)]
+pub fn day13_part2(instructions: &Vec<InstructionByte>) -> Result<InstructionByte, ArcadeError> {
+    let mut screen = Screen::default();
+    let mut interp = IntCodeInterpreter::new(instructions);
+    // Two quarters for free play
+    interp.memory[0] = 2;
+    play(&mut interp, &mut screen, track_ball, |_| {})?;
+    Ok(screen.score)
+}
@@ -0,0 +1,202 @@
+use super::grid::{Grid, Pos};
+use super::{Action, InstructionByte, IntCodeInterpreter, IntcodeError};
+use std::error::Error;
+use std::fmt;
+
+/// The tiles drawn by the arcade cabinet in 2019 day 13.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArcadeError {
+    Machine(IntcodeError),
+    BadTile(InstructionByte),
+}
+
+impl fmt::Display for ArcadeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArcadeError::Machine(e) => write!(f, "{}", e),
+            ArcadeError::BadTile(id) => write!(f, "unknown tile {}", id),
+        }
+    }
+}
+
+impl Error for ArcadeError {}
+
+impl From<IntcodeError> for ArcadeError {
+    fn from(e: IntcodeError) -> Self {
+        ArcadeError::Machine(e)
+    }
+}
+
+/// What the arcade cabinet is showing.
+#[derive(Debug, Default, Clone)]
+pub struct Screen {
+    pub tiles: Grid<Tile>,
+    pub score: InstructionByte,
+    pub ball: Option<Pos>,
+    pub paddle: Option<Pos>,
+}
+
+impl Screen {
+    /// Carries out one `x, y, tile` draw command. Drawing at `-1, 0` sets
+    /// the score instead.
+    pub fn draw(
+        &mut self,
+        x: InstructionByte,
+        y: InstructionByte,
+        id: InstructionByte,
+    ) -> Result<(), ArcadeError> {
+        if (x, y) == (-1, 0) {
+            self.score = id;
+            return Ok(());
+        }
+
+        let tile = match id {
+            0 => Tile::Empty,
+            1 => Tile::Wall,
+            2 => Tile::Block,
+            3 => Tile::Paddle,
+            4 => Tile::Ball,
+            _ => return Err(ArcadeError::BadTile(id)),
+        };
+        let pos = Pos::new(y as isize, x as isize);
+        match tile {
+            Tile::Ball => self.ball = Some(pos),
+            Tile::Paddle => self.paddle = Some(pos),
+            _ => {}
+        }
+        self.tiles.set(pos, tile);
+        Ok(())
+    }
+
+    pub fn count(&self, tile: Tile) -> usize {
+        self.tiles.cells.values().filter(|t| **t == tile).count()
+    }
+
+    pub fn render(&self) -> String {
+        let tiles = self.tiles.render(|tile| match tile {
+            Some(Tile::Wall) => '#',
+            Some(Tile::Block) => '=',
+            Some(Tile::Paddle) => '-',
+            Some(Tile::Ball) => 'o',
+            Some(Tile::Empty) | None => ' ',
+        });
+        format!("Score: {}\n{}", self.score, tiles)
+    }
+}
+
+/// A text renderer for `play`, which prints every frame.
+pub fn print_frame(screen: &Screen) {
+    println!("{}\n", screen.render());
+}
+
+/// Moves the joystick towards the ball: -1 for left, 1 for right, or 0 to
+/// stay put.
+pub fn track_ball(screen: &Screen) -> InstructionByte {
+    match (screen.ball, screen.paddle) {
+        (Some(ball), Some(paddle)) => (ball.col - paddle.col).signum() as InstructionByte,
+        _ => 0,
+    }
+}
+
+/// Runs the game until it halts, asking `joystick` for a position whenever
+/// the program reads one. `on_frame` sees the screen each time the game
+/// waits for the joystick, and once more at the end.
+pub fn play<J, F>(
+    interp: &mut IntCodeInterpreter,
+    screen: &mut Screen,
+    mut joystick: J,
+    mut on_frame: F,
+) -> Result<(), ArcadeError>
+where
+    J: FnMut(&Screen) -> InstructionByte,
+    F: FnMut(&Screen),
+{
+    let mut command = Vec::new();
+    loop {
+        match interp.run()? {
+            Action::Output(value) => {
+                command.push(value);
+                if command.len() == 3 {
+                    screen.draw(command[0], command[1], command[2])?;
+                    command.clear();
+                }
+            }
+            Action::NeedInput => {
+                on_frame(screen);
+                interp.write_input(joystick(screen));
+            }
+            Action::Halt => {
+                on_frame(screen);
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[test]
+pub fn screen_test() {
+    let mut screen = Screen::default();
+    for command in [[1, 2, 3], [6, 5, 4], [-1, 0, 12345]].iter() {
+        screen.draw(command[0], command[1], command[2]).unwrap();
+    }
+    assert_eq!(screen.paddle, Some(Pos::new(2, 1)));
+    assert_eq!(screen.ball, Some(Pos::new(5, 6)));
+    assert_eq!(screen.score, 12345);
+    assert_eq!(screen.count(Tile::Paddle), 1);
+    assert_eq!(screen.draw(0, 0, 5), Err(ArcadeError::BadTile(5)));
+}
+
+#[test]
+pub fn play_test() {
+    // The ball sits at x = 3 while the paddle follows the joystick from
+    // x = 0, and the score shows where the paddle is. The game ends when
+    // the joystick is left in the middle.
+    let program = super::assembler::assemble(
+        "
+        loop:   OUT #-1
+                OUT #0
+                OUT [px]
+                OUT [px]
+                OUT #1
+                OUT #3
+                OUT #3
+                OUT #0
+                OUT #4
+                IN -> [j]
+                JZ [j], #end
+                OUT [px]
+                OUT #1
+                OUT #0
+                ADD [px], [j] -> [px]
+                JZ #0, #loop
+        end:    HALT
+        px:     DATA 0
+        j:      DATA 0
+        ",
+    )
+    .unwrap()
+    .words;
+
+    let mut screen = Screen::default();
+    let mut frames = Vec::new();
+    let mut interp = IntCodeInterpreter::new(&program);
+    play(&mut interp, &mut screen, track_ball, |screen| {
+        frames.push(screen.render())
+    })
+    .unwrap();
+
+    assert_eq!(screen.score, 3);
+    assert_eq!(screen.paddle, Some(Pos::new(1, 3)));
+    assert_eq!(frames.len(), 5);
+    assert_eq!(frames[0], "Score: 0\n   o\n-   ");
+    assert_eq!(frames[4], "Score: 3\n   o\n   -");
+}
@@ -0,0 +1,145 @@
+use super::{Action, InstructionByte, IntCodeInterpreter, IntcodeError};
+
+/// What a program printed before it stopped for input or halted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AsciiOutput {
+    /// Printed text split into lines. A final line without a newline, such
+    /// as a prompt, is included too.
+    pub lines: Vec<String>,
+    /// Outputs outside the ASCII range, which are usually puzzle answers.
+    pub values: Vec<InstructionByte>,
+    pub halted: bool,
+}
+
+/// The result of feeding a program a script of commands.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    /// Everything the program printed, with each command echoed after the
+    /// prompt that asked for it, as it would appear on a terminal.
+    pub text: String,
+    pub values: Vec<InstructionByte>,
+    /// False if the script ran out while the program still wanted input.
+    pub halted: bool,
+}
+
+/// Talks to a program that reads and writes ASCII text.
+pub struct AsciiConsole {
+    pub interp: IntCodeInterpreter,
+}
+
+impl AsciiConsole {
+    pub fn new(interp: IntCodeInterpreter) -> Self {
+        Self { interp }
+    }
+
+    /// Queues `line` as input, followed by a newline.
+    pub fn send_line(&mut self, line: &str) {
+        for b in line.bytes() {
+            self.interp.write_input(b as InstructionByte);
+        }
+        self.interp.write_input(b'\n' as InstructionByte);
+    }
+
+    fn run_raw(
+        &mut self,
+        text: &mut String,
+        values: &mut Vec<InstructionByte>,
+    ) -> Result<bool, IntcodeError> {
+        loop {
+            match self.interp.run()? {
+                Action::Output(value) if (0..=127).contains(&value) => {
+                    text.push(value as u8 as char)
+                }
+                Action::Output(value) => values.push(value),
+                Action::NeedInput => return Ok(false),
+                Action::Halt => return Ok(true),
+            }
+        }
+    }
+
+    /// Runs until the program needs more input or halts.
+    pub fn run(&mut self) -> Result<AsciiOutput, IntcodeError> {
+        let mut text = String::new();
+        let mut values = Vec::<InstructionByte>::new();
+        let halted = self.run_raw(&mut text, &mut values)?;
+
+        Ok(AsciiOutput {
+            lines: text.lines().map(str::to_string).collect(),
+            values,
+            halted,
+        })
+    }
+
+    /// Runs the program, sending the next line of `script` each time it asks
+    /// for input, until it halts or the script runs out.
+    pub fn run_script<'a, I>(&mut self, script: I) -> Result<Transcript, IntcodeError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut transcript = Transcript::default();
+        let mut script = script.into_iter();
+
+        loop {
+            transcript.halted = self.run_raw(&mut transcript.text, &mut transcript.values)?;
+            if transcript.halted {
+                break;
+            }
+
+            match script.next() {
+                Some(line) => {
+                    transcript.text.push_str(line);
+                    transcript.text.push('\n');
+                    self.send_line(line);
+                }
+                None => break,
+            }
+        }
+
+        Ok(transcript)
+    }
+}
+
+#[test]
+pub fn console_test() {
+    // Prints a prompt, echoes one line of input in upper case, then prints 1000
+    let program = super::assembler::assemble(
+        "
+                OUT #62         ; >
+        loop:   IN -> [c]
+                LT [c], #97 -> [t]
+                JNZ [t], #print
+                ADD [c], #-32 -> [c]
+        print:  OUT [c]
+                EQ [c], #10 -> [t]
+                JZ [t], #loop
+                OUT #1000
+                HALT
+        c:      DATA 0
+        t:      DATA 0
+        ",
+    )
+    .unwrap()
+    .words;
+
+    let mut console = AsciiConsole::new(IntCodeInterpreter::new(&program));
+    let output = console.run().unwrap();
+    assert_eq!(output.lines, vec![">"]);
+    assert!(!output.halted);
+
+    console.send_line("hi there");
+    let output = console.run().unwrap();
+    assert_eq!(output.lines, vec!["HI THERE"]);
+    assert_eq!(output.values, vec![1000]);
+    assert!(output.halted);
+
+    let mut console = AsciiConsole::new(IntCodeInterpreter::new(&program));
+    let transcript = console.run_script(vec!["north", "unused"]).unwrap();
+    assert_eq!(transcript.text, ">north\nNORTH\n");
+    assert_eq!(transcript.values, vec![1000]);
+    assert!(transcript.halted);
+
+    let mut console = AsciiConsole::new(IntCodeInterpreter::new(&program));
+    let transcript = console.run_script(vec![]).unwrap();
+    assert_eq!(transcript.text, ">");
+    assert!(!transcript.halted);
+}
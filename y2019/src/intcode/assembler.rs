@@ -0,0 +1,444 @@
+use super::disassembler::listing_lines_split_at;
+use super::{Instruction, InstructionByte, InstructionBytes, Parameter};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// An assembled program along with the addresses of its labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assembly {
+    pub words: InstructionBytes,
+    pub labels: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssemblerErrorKind {
+    UnknownMnemonic(String),
+    BadOperand(String),
+    WrongOperandCount { expected: usize, found: usize },
+    WriteToImmediate,
+    UndefinedLabel(String),
+    DuplicateLabel(String),
+    AddressMismatch { expected: usize, found: usize },
+}
+
+/// A problem found on (1-based) source line `line`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblerError {
+    pub line: usize,
+    pub kind: AssemblerErrorKind,
+}
+
+impl fmt::Display for AssemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl fmt::Display for AssemblerErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssemblerErrorKind::UnknownMnemonic(m) => write!(f, "unknown mnemonic '{}'", m),
+            AssemblerErrorKind::BadOperand(o) => write!(f, "bad operand '{}'", o),
+            AssemblerErrorKind::WrongOperandCount { expected, found } => {
+                write!(f, "expected {} operands, found {}", expected, found)
+            }
+            AssemblerErrorKind::WriteToImmediate => write!(f, "cannot write to an immediate"),
+            AssemblerErrorKind::UndefinedLabel(l) => write!(f, "undefined label '{}'", l),
+            AssemblerErrorKind::DuplicateLabel(l) => write!(f, "duplicate label '{}'", l),
+            AssemblerErrorKind::AddressMismatch { expected, found } => {
+                write!(f, "line is at address {}, not {}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for AssemblerError {}
+
+#[derive(Debug)]
+enum Value {
+    Number(InstructionByte),
+    Label(String),
+}
+
+#[derive(Debug)]
+struct Operand {
+    mode: InstructionByte,
+    value: Value,
+}
+
+#[derive(Debug)]
+enum Statement {
+    Instruction(String, Vec<Operand>),
+    Data(Vec<Value>),
+}
+
+/// (mnemonic, operand count, index of the operand that's written to)
+const MNEMONICS: [(&str, usize, Option<usize>); 10] = [
+    ("ADD", 3, Some(2)),
+    ("MUL", 3, Some(2)),
+    ("IN", 1, Some(0)),
+    ("OUT", 1, None),
+    ("JNZ", 2, None),
+    ("JZ", 2, None),
+    ("LT", 3, Some(2)),
+    ("EQ", 3, Some(2)),
+    ("ARB", 1, None),
+    ("HALT", 0, None),
+];
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_value(s: &str) -> Result<Value, AssemblerErrorKind> {
+    let s = s.trim();
+    if let Ok(n) = s.parse::<InstructionByte>() {
+        Ok(Value::Number(n))
+    } else if is_identifier(s) {
+        Ok(Value::Label(s.to_string()))
+    } else {
+        Err(AssemblerErrorKind::BadOperand(s.to_string()))
+    }
+}
+
+/// Parses `#imm`, `[pos]`, `[r+n]`/`[rb+n]` or `[r-n]`/`[rb-n]`.
+fn parse_operand(s: &str) -> Result<Operand, AssemblerErrorKind> {
+    let s = s.trim();
+    let bad = || AssemblerErrorKind::BadOperand(s.to_string());
+
+    if let Some(imm) = s.strip_prefix('#') {
+        return Ok(Operand {
+            mode: 1,
+            value: parse_value(imm).map_err(|_| bad())?,
+        });
+    }
+
+    let inner = s
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(bad)?
+        .trim();
+
+    let relative = inner
+        .strip_prefix("rb")
+        .or_else(|| inner.strip_prefix('r'))
+        .map(str::trim_start)
+        .filter(|rest| rest.starts_with('+') || rest.starts_with('-'));
+
+    match relative {
+        Some(offset) => {
+            let value = match offset.strip_prefix('+') {
+                Some(positive) => parse_value(positive).map_err(|_| bad())?,
+                None => match parse_value(&offset[1..]) {
+                    Ok(Value::Number(n)) => Value::Number(n.checked_neg().ok_or_else(bad)?),
+                    _ => return Err(bad()),
+                },
+            };
+            Ok(Operand { mode: 2, value })
+        }
+        None => Ok(Operand {
+            mode: 0,
+            value: parse_value(inner).map_err(|_| bad())?,
+        }),
+    }
+}
+
+fn parse_statement(text: &str) -> Result<Statement, AssemblerErrorKind> {
+    let (mnemonic, rest) = match text.find(char::is_whitespace) {
+        Some(idx) => (&text[..idx], text[idx..].trim()),
+        None => (text, ""),
+    };
+    let mnemonic = mnemonic.to_uppercase();
+
+    // Treat the "->" before an output operand as just another separator
+    let operands = rest.replace("->", ",");
+    let operands = operands
+        .split(',')
+        .map(str::trim)
+        .filter(|op| !op.is_empty())
+        .collect::<Vec<_>>();
+
+    if mnemonic == "DATA" {
+        let values = operands
+            .iter()
+            .map(|op| parse_value(op))
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(Statement::Data(values));
+    }
+
+    let (_, count, output) = MNEMONICS
+        .iter()
+        .find(|(name, _, _)| *name == mnemonic)
+        .ok_or_else(|| AssemblerErrorKind::UnknownMnemonic(mnemonic.clone()))?;
+
+    if operands.len() != *count {
+        return Err(AssemblerErrorKind::WrongOperandCount {
+            expected: *count,
+            found: operands.len(),
+        });
+    }
+
+    let operands = operands
+        .iter()
+        .map(|op| parse_operand(op))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(output) = output {
+        if operands[*output].mode == 1 {
+            return Err(AssemblerErrorKind::WriteToImmediate);
+        }
+    }
+
+    Ok(Statement::Instruction(mnemonic, operands))
+}
+
+impl Statement {
+    fn size(&self) -> usize {
+        match self {
+            Statement::Instruction(_, operands) => 1 + operands.len(),
+            Statement::Data(values) => values.len(),
+        }
+    }
+}
+
+fn resolve(
+    value: &Value,
+    labels: &BTreeMap<String, usize>,
+) -> Result<InstructionByte, AssemblerErrorKind> {
+    match value {
+        Value::Number(n) => Ok(*n),
+        Value::Label(l) => labels
+            .get(l)
+            .map(|addr| *addr as InstructionByte)
+            .ok_or_else(|| AssemblerErrorKind::UndefinedLabel(l.clone())),
+    }
+}
+
+fn encode(
+    statement: &Statement,
+    labels: &BTreeMap<String, usize>,
+) -> Result<InstructionBytes, AssemblerErrorKind> {
+    let (mnemonic, operands) = match statement {
+        Statement::Data(values) => {
+            return values.iter().map(|v| resolve(v, labels)).collect();
+        }
+        Statement::Instruction(mnemonic, operands) => (mnemonic, operands),
+    };
+
+    let mut params = operands
+        .iter()
+        .map(|op| {
+            let value = resolve(&op.value, labels)?;
+            Ok(Parameter::new(op.mode, value).unwrap())
+        })
+        .collect::<Result<Vec<_>, AssemblerErrorKind>>()?
+        .into_iter();
+    let mut p = || params.next().unwrap();
+
+    let inst = match mnemonic.as_str() {
+        "ADD" => Instruction::Add(p(), p(), p()),
+        "MUL" => Instruction::Multiply(p(), p(), p()),
+        "IN" => Instruction::Input(p()),
+        "OUT" => Instruction::Output(p()),
+        "JNZ" => Instruction::JumpIfTrue(p(), p()),
+        "JZ" => Instruction::JumpIfFalse(p(), p()),
+        "LT" => Instruction::LessThan(p(), p(), p()),
+        "EQ" => Instruction::Equals(p(), p(), p()),
+        "ARB" => Instruction::AdjustRelativeBase(p()),
+        "HALT" => Instruction::Halt,
+        _ => unreachable!("mnemonic checked during parsing"),
+    };
+
+    Ok(inst.encode())
+}
+
+/// Assembles mnemonic source into Intcode.
+///
+/// Each line is `[label:]... [statement] [; comment]`, where a statement is
+/// one of the mnemonics printed by the disassembler (`ADD #1, [x] -> [r+2]`)
+/// or `DATA` followed by comma-separated values. Values may be numbers or
+/// labels. A numeric label such as `0012:` is an address annotation and must
+/// match the current address, so a disassembly listing assembles back into
+/// the original words.
+pub fn assemble(source: &str) -> Result<Assembly, AssemblerError> {
+    let mut labels = BTreeMap::<String, usize>::new();
+    let mut statements = Vec::<(usize, Statement)>::new();
+    let mut addr = 0;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let err = |kind| AssemblerError {
+            line: line_no,
+            kind,
+        };
+
+        let mut text = line.split(';').next().unwrap().trim();
+
+        while let Some(colon) = text.find(':') {
+            let label = text[..colon].trim();
+            if let Ok(found) = label.parse::<usize>() {
+                if found != addr {
+                    return Err(err(AssemblerErrorKind::AddressMismatch {
+                        expected: addr,
+                        found,
+                    }));
+                }
+            } else if is_identifier(label) {
+                if labels.insert(label.to_string(), addr).is_some() {
+                    return Err(err(AssemblerErrorKind::DuplicateLabel(label.to_string())));
+                }
+            } else {
+                break;
+            }
+            text = text[colon + 1..].trim();
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let statement = parse_statement(text).map_err(err)?;
+        addr += statement.size();
+        statements.push((line_no, statement));
+    }
+
+    let mut words = InstructionBytes::new();
+    for (line, statement) in &statements {
+        let encoded =
+            encode(statement, &labels).map_err(|kind| AssemblerError { line: *line, kind })?;
+        words.extend(encoded);
+    }
+
+    Ok(Assembly { words, labels })
+}
+
+impl Assembly {
+    /// A disassembly listing of the words with label definitions interleaved.
+    /// Assembling the listing gives back the same words and labels.
+    pub fn listing(&self) -> String {
+        let mut by_addr = HashMap::<usize, Vec<&str>>::new();
+        for (label, addr) in &self.labels {
+            by_addr.entry(*addr).or_default().push(label);
+        }
+
+        let mut lines = Vec::<String>::new();
+        let mut emit_labels = |addr: usize, lines: &mut Vec<String>| {
+            if let Some(names) = by_addr.remove(&addr) {
+                lines.extend(names.iter().map(|name| format!("{}:", name)));
+            }
+        };
+
+        // A label inside an instruction would be lost, so those words are
+        // listed as data instead
+        let boundaries: HashSet<usize> = self.labels.values().copied().collect();
+        for (addr, text) in listing_lines_split_at(&self.words, &boundaries) {
+            emit_labels(addr, &mut lines);
+            lines.push(format!("{:04}: {}", addr, text));
+        }
+        // Labels can point one past the end of the program
+        emit_labels(self.words.len(), &mut lines);
+
+        lines.join("\n")
+    }
+}
+
+#[test]
+pub fn assemble_test() {
+    let source = "
+        ; Count down from 3, outputting each value
+        start:  IN -> [count]
+        loop:   OUT [count]
+                ADD [count], #-1 -> [count]
+                JNZ [count], #loop
+                HALT
+        count:  DATA 0
+    ";
+    let asm = assemble(source).unwrap();
+    assert_eq!(
+        asm.words,
+        vec![3, 12, 4, 12, 1001, 12, -1, 12, 1005, 12, 2, 99, 0]
+    );
+    assert_eq!(asm.labels["loop"], 2);
+    assert_eq!(asm.labels["count"], 12);
+
+    let mut prog = super::IntCodeInterpreter::new(&asm.words);
+    prog.write_input(3);
+    let mut outputs = Vec::<InstructionByte>::new();
+    while let super::Action::Output(o) = prog.run().unwrap() {
+        outputs.push(o);
+    }
+    assert_eq!(outputs, vec![3, 2, 1]);
+
+    assert_eq!(assemble(&asm.listing()).unwrap(), asm);
+}
+
+#[test]
+pub fn assemble_modes_test() {
+    let asm =
+        assemble("add [rb+3], #5 -> [100]\nmul [r-1], [rb - 2], [r+x]\nx: data 7, x").unwrap();
+    assert_eq!(asm.words, vec![1201, 3, 5, 100, 22202, -1, -2, 8, 7, 8]);
+}
+
+#[test]
+pub fn listing_round_trip_test() {
+    let quine = vec![
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ];
+    let listing = super::disassembler::disassemble(&quine);
+    assert_eq!(assemble(&listing).unwrap().words, quine);
+
+    // b points inside what would otherwise be listed as an ADD
+    let asm = assemble("a: DATA 1\nb: DATA 1, 2, 3").unwrap();
+    let reassembled = assemble(&asm.listing()).unwrap();
+    assert_eq!(reassembled, asm);
+    assert_eq!(reassembled.labels["b"], 1);
+}
+
+#[test]
+pub fn assemble_errors_test() {
+    let kind = |source: &str| assemble(source).unwrap_err().kind;
+
+    assert_eq!(
+        kind("NOP"),
+        AssemblerErrorKind::UnknownMnemonic("NOP".to_string())
+    );
+    assert_eq!(
+        kind("OUT 5"),
+        AssemblerErrorKind::BadOperand("5".to_string())
+    );
+    assert_eq!(
+        kind("ADD #1, #2"),
+        AssemblerErrorKind::WrongOperandCount {
+            expected: 3,
+            found: 2
+        }
+    );
+    assert_eq!(kind("IN -> #4"), AssemblerErrorKind::WriteToImmediate);
+    let negated_min = format!("[r-{}]", InstructionByte::MIN);
+    assert_eq!(
+        kind(&format!("OUT {}", negated_min)),
+        AssemblerErrorKind::BadOperand(negated_min)
+    );
+    assert_eq!(
+        kind("JZ #0, #nowhere"),
+        AssemblerErrorKind::UndefinedLabel("nowhere".to_string())
+    );
+    assert_eq!(
+        kind("a: HALT\na: HALT"),
+        AssemblerErrorKind::DuplicateLabel("a".to_string())
+    );
+    assert_eq!(
+        kind("HALT\n0002: HALT"),
+        AssemblerErrorKind::AddressMismatch {
+            expected: 1,
+            found: 2
+        }
+    );
+    assert_eq!(assemble("HALT\nfoo bar").unwrap_err().line, 2);
+}
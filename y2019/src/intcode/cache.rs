@@ -0,0 +1,84 @@
+use super::memory::FLAT_LIMIT;
+use super::Instruction;
+
+/// Decoded instructions by address, so that loops don't pay for splitting
+/// out opcodes and modes on every pass. Entries are dropped when any word
+/// they were decoded from is written.
+#[derive(Debug, Default, Clone)]
+pub struct DecodeCache {
+    entries: Vec<Option<(Instruction, usize)>>,
+}
+
+impl DecodeCache {
+    pub fn get(&self, ip: usize) -> Option<&(Instruction, usize)> {
+        self.entries.get(ip).and_then(Option::as_ref)
+    }
+
+    /// Caches the instruction at `ip`, unless it's far out in paged memory.
+    pub fn insert(&mut self, ip: usize, instruction: Instruction, size: usize) {
+        if ip >= FLAT_LIMIT {
+            return;
+        }
+        if ip >= self.entries.len() {
+            self.entries.resize(ip + 1, None);
+        }
+        self.entries[ip] = Some((instruction, size));
+    }
+
+    /// Drops any instruction that covers `address`.
+    pub fn invalidate(&mut self, address: usize) {
+        for ip in Instruction::covering(address, self.entries.len()) {
+            if let Some((_, size)) = self.entries[ip] {
+                if ip + size > address {
+                    self.entries[ip] = None;
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[test]
+pub fn self_modifying_test() {
+    use super::IntCodeInterpreter;
+
+    // OUT #1; EQ [1], #2 -> [17]; JNZ [17], #16; ADD #1, #1 -> [1]; JZ #0, #0; HALT
+    // The first pass outputs 1 then rewrites the OUT's operand to 2, so the
+    // second pass must see the new operand rather than the cached one
+    let program = vec![
+        104, 1, 1008, 1, 2, 17, 1005, 17, 16, 1101, 1, 1, 1, 1106, 0, 0, 99, 0,
+    ];
+
+    let mut prog = IntCodeInterpreter::new(&program);
+    prog.set_decode_cache(true);
+    assert_eq!(prog.run_to_completion(vec![]), Ok(vec![1, 2]));
+
+    let mut cache = DecodeCache::default();
+    cache.insert(4, Instruction::Halt, 1);
+    cache.insert(0, Instruction::Halt, 4);
+    cache.invalidate(4);
+    assert!(cache.get(4).is_none());
+    assert!(cache.get(0).is_some());
+    cache.invalidate(3);
+    assert!(cache.get(0).is_none());
+    cache.invalidate(1000);
+    cache.invalidate(usize::MAX);
+
+    // Nothing decoded yet
+    DecodeCache::default().invalidate(0);
+
+    // The first write lands before the compiled ADD has been through the
+    // cache at all
+    let mut prog = IntCodeInterpreter::new(&vec![1101, 1, 1, 0, 99]);
+    prog.set_decode_cache(true);
+    prog.set_compiled(true);
+    assert_eq!(prog.run_to_completion(vec![]), Ok(vec![]));
+    assert_eq!(prog.memory.get(0), 2);
+
+    let mut cache = DecodeCache::default();
+    cache.insert(1 << 40, Instruction::Halt, 1);
+    assert!(cache.get(1 << 40).is_none());
+}
@@ -0,0 +1,165 @@
+use super::{Action, InstructionByte, IntCodeInterpreter, IntcodeError, IntcodeErrorKind};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+impl IntCodeInterpreter {
+    /// Runs until the program halts, blocking on `input` whenever it needs a
+    /// value and sending everything it outputs to `output`. Fails with
+    /// `InputExhausted` if the program wants input after every sender has
+    /// hung up. If the receiver of `output` hangs up, the program is stopped
+    /// where it is, as nobody is listening.
+    pub fn run_on_channels(
+        &mut self,
+        input: &Receiver<InstructionByte>,
+        output: &Sender<InstructionByte>,
+    ) -> Result<(), IntcodeError> {
+        loop {
+            match self.run()? {
+                Action::Output(value) => {
+                    if output.send(value).is_err() {
+                        return Ok(());
+                    }
+                }
+                Action::NeedInput => match input.recv() {
+                    Ok(value) => self.write_input(value),
+                    Err(_) => {
+                        return Err(IntcodeError {
+                            ip: self.ip,
+                            instruction: self.memory.get(self.ip),
+                            kind: IntcodeErrorKind::InputExhausted,
+                        })
+                    }
+                },
+                Action::Halt => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Runs `interp` on its own thread, reading from `input` and writing to
+/// `output`, so machines can be wired straight into each other. The thread
+/// gives back the interpreter once it stops.
+pub fn spawn_connected(
+    mut interp: IntCodeInterpreter,
+    input: Receiver<InstructionByte>,
+    output: Sender<InstructionByte>,
+) -> JoinHandle<Result<IntCodeInterpreter, IntcodeError>> {
+    thread::spawn(move || {
+        interp.run_on_channels(&input, &output)?;
+        Ok(interp)
+    })
+}
+
+/// A machine running on its own thread, driven by a host-side controller
+/// through a pair of channels.
+pub struct Machine {
+    pub input: Sender<InstructionByte>,
+    /// Disconnects once the machine stops, so iterating over it reads every
+    /// output up to the halt.
+    pub output: Receiver<InstructionByte>,
+    handle: JoinHandle<Result<IntCodeInterpreter, IntcodeError>>,
+}
+
+impl Machine {
+    pub fn spawn(interp: IntCodeInterpreter) -> Self {
+        let (input, machine_input) = channel();
+        let (machine_output, output) = channel();
+        Self {
+            input,
+            output,
+            handle: spawn_connected(interp, machine_input, machine_output),
+        }
+    }
+
+    /// Queues a value for the machine. Returns false if it has stopped.
+    pub fn send(&self, value: InstructionByte) -> bool {
+        self.input.send(value).is_ok()
+    }
+
+    /// Waits for the machine's next output, or `None` if it has stopped.
+    pub fn recv(&self) -> Option<InstructionByte> {
+        self.output.recv().ok()
+    }
+
+    /// Hangs up the input channel and waits for the machine to stop, so a
+    /// machine still waiting for input fails rather than blocking forever.
+    pub fn join(self) -> Result<IntCodeInterpreter, IntcodeError> {
+        drop(self.input);
+        drop(self.output);
+        self.handle.join().expect("intcode thread panicked")
+    }
+}
+
+#[test]
+pub fn machine_test() {
+    // Outputs each input plus one, until it reads a zero
+    let program = super::assembler::assemble(
+        "
+        loop:   IN -> [x]
+                JZ [x], #end
+                ADD [x], #1 -> [x]
+                OUT [x]
+                JZ #0, #loop
+        end:    HALT
+        x:      DATA 0
+        ",
+    )
+    .unwrap()
+    .words;
+
+    let machine = Machine::spawn(IntCodeInterpreter::new(&program));
+    assert!(machine.send(1));
+    assert_eq!(machine.recv(), Some(2));
+    assert!(machine.send(41));
+    assert_eq!(machine.recv(), Some(42));
+    assert!(machine.send(0));
+    assert_eq!(machine.recv(), None);
+    assert!(machine.join().is_ok());
+
+    let machine = Machine::spawn(IntCodeInterpreter::new(&program));
+    machine.send(5);
+    assert_eq!(machine.recv(), Some(6));
+    let err = machine.join().err().unwrap();
+    assert_eq!(err.kind, IntcodeErrorKind::InputExhausted);
+}
+
+#[test]
+pub fn feedback_test() {
+    // Amplifier feedback loop example from 2019 day 7, with the host
+    // watching the signal on its way from the last amplifier to the first
+    let program = vec![
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+    let phases = [9, 8, 7, 6, 5];
+
+    let (senders, receivers): (Vec<_>, Vec<_>) = phases.iter().map(|_| channel()).unzip();
+    let (tap_sender, tap) = channel();
+    for (sender, phase) in senders.iter().zip(phases.iter()) {
+        sender.send(*phase).unwrap();
+    }
+    senders[0].send(0).unwrap();
+
+    let handles = receivers
+        .into_iter()
+        .enumerate()
+        .map(|(i, input)| {
+            let output = match senders.get(i + 1) {
+                Some(next) => next.clone(),
+                None => tap_sender.clone(),
+            };
+            spawn_connected(IntCodeInterpreter::new(&program), input, output)
+        })
+        .collect::<Vec<_>>();
+    drop(tap_sender);
+
+    let mut last = None;
+    for value in tap {
+        last = Some(value);
+        let _ = senders[0].send(value);
+    }
+    for handle in handles {
+        assert!(handle.join().unwrap().is_ok());
+    }
+    assert_eq!(last, Some(139629729));
+}
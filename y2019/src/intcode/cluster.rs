@@ -0,0 +1,405 @@
+use super::{Action, InstructionByte, IntCodeInterpreter, IntcodeError, MemoryData};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+/// How each machine's outputs are routed to the other machines' inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Topology {
+    /// Machine i feeds machine i + 1, and the last machine wraps round to the
+    /// first, as in an amplifier feedback loop.
+    Ring,
+    /// Every output goes to every other machine.
+    Broadcast,
+    /// Outputs are `address, X, Y` triples, and X then Y are delivered to the
+    /// machine at `address`.
+    Packet,
+    /// Machine i's outputs go to each of the machines listed in `edges[i]`.
+    Custom(Vec<Vec<usize>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterEvent {
+    /// Every machine has halted.
+    AllHalted,
+    /// A full round passed with no messages sent and no NAT to wake things up.
+    Idle,
+    /// A packet was sent to the NAT address.
+    NatReceived {
+        x: InstructionByte,
+        y: InstructionByte,
+    },
+    /// The network was idle, so the NAT resent its last packet to machine 0.
+    NatSent {
+        x: InstructionByte,
+        y: InstructionByte,
+    },
+}
+
+/// What's wrong with a `Topology::Custom`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopologyError {
+    /// There are edge lists for `edges` machines rather than one per machine.
+    EdgeCount { machines: usize, edges: usize },
+    /// An edge goes to a machine that doesn't exist.
+    MissingMachine { from: usize, to: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterError {
+    Machine(usize, IntcodeError),
+    UnknownAddress {
+        from: usize,
+        address: InstructionByte,
+    },
+    InvalidTopology(TopologyError),
+}
+
+impl fmt::Display for ClusterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClusterError::Machine(index, e) => write!(f, "machine {}: {}", index, e),
+            ClusterError::UnknownAddress { from, address } => {
+                write!(f, "machine {} sent to unknown address {}", from, address)
+            }
+            ClusterError::InvalidTopology(TopologyError::EdgeCount { machines, edges }) => write!(
+                f,
+                "custom topology has edges for {} machines, not {}",
+                edges, machines
+            ),
+            ClusterError::InvalidTopology(TopologyError::MissingMachine { from, to }) => write!(
+                f,
+                "custom topology has an edge from machine {} to missing machine {}",
+                from, to
+            ),
+        }
+    }
+}
+
+impl Error for ClusterError {}
+
+struct Nat {
+    address: InstructionByte,
+    last: Option<(InstructionByte, InstructionByte)>,
+}
+
+/// Runs a set of machines round-robin, passing messages between them
+/// according to a `Topology`. Each machine runs until it needs input or
+/// halts before the next machine gets a turn.
+pub struct Cluster {
+    pub machines: Vec<IntCodeInterpreter>,
+    topology: Topology,
+    halted: Vec<bool>,
+    last_output: Vec<Option<InstructionByte>>,
+    partial_packets: Vec<MemoryData>,
+    idle_input: Option<InstructionByte>,
+    nat: Option<Nat>,
+    next_machine: usize,
+    round_active: bool,
+    events: VecDeque<ClusterEvent>,
+}
+
+impl Cluster {
+    /// Fails if a `Topology::Custom` doesn't list edges for exactly the
+    /// machines given, or has an edge to a machine that doesn't exist.
+    pub fn new(
+        machines: Vec<IntCodeInterpreter>,
+        topology: Topology,
+    ) -> Result<Self, ClusterError> {
+        let count = machines.len();
+        if let Topology::Custom(edges) = &topology {
+            if edges.len() != count {
+                return Err(ClusterError::InvalidTopology(TopologyError::EdgeCount {
+                    machines: count,
+                    edges: edges.len(),
+                }));
+            }
+            for (from, targets) in edges.iter().enumerate() {
+                if let Some(&to) = targets.iter().find(|&&to| to >= count) {
+                    return Err(ClusterError::InvalidTopology(
+                        TopologyError::MissingMachine { from, to },
+                    ));
+                }
+            }
+        }
+        Ok(Self::unchecked(machines, topology))
+    }
+
+    fn unchecked(machines: Vec<IntCodeInterpreter>, topology: Topology) -> Self {
+        let count = machines.len();
+        Self {
+            machines,
+            topology,
+            halted: vec![false; count],
+            last_output: vec![None; count],
+            partial_packets: vec![Vec::new(); count],
+            idle_input: None,
+            nat: None,
+            next_machine: 0,
+            round_active: false,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// A packet-switched network of `count` copies of `program`, each booted
+    /// with its own address, which reads `-1` when it has no packets waiting.
+    pub fn network(program: &MemoryData, count: usize) -> Self {
+        let machines = (0..count)
+            .map(|address| {
+                let mut machine = IntCodeInterpreter::new(program);
+                machine.write_input(address as InstructionByte);
+                machine
+            })
+            .collect();
+        Cluster::unchecked(machines, Topology::Packet).with_idle_input(-1)
+    }
+
+    /// A value given to a machine that is blocked on an empty queue, once per turn.
+    pub fn with_idle_input(mut self, value: InstructionByte) -> Self {
+        self.idle_input = Some(value);
+        self
+    }
+
+    /// Packets to `address` are held by a NAT, which resends the last one to
+    /// machine 0 whenever the whole network goes idle.
+    pub fn with_nat(mut self, address: InstructionByte) -> Self {
+        self.nat = Some(Nat {
+            address,
+            last: None,
+        });
+        self
+    }
+
+    pub fn send(&mut self, to: usize, value: InstructionByte) {
+        self.machines[to].write_input(value);
+        self.round_active = true;
+    }
+
+    /// The most recent value output by machine `index`.
+    pub fn last_output(&self, index: usize) -> Option<InstructionByte> {
+        self.last_output[index]
+    }
+
+    pub fn is_halted(&self, index: usize) -> bool {
+        self.halted[index]
+    }
+
+    fn route(&mut self, from: usize, value: InstructionByte) -> Result<(), ClusterError> {
+        let count = self.machines.len();
+        self.last_output[from] = Some(value);
+
+        let targets = match &self.topology {
+            Topology::Ring => vec![(from + 1) % count],
+            Topology::Broadcast => (0..count).filter(|&to| to != from).collect(),
+            Topology::Custom(edges) => edges[from].clone(),
+            Topology::Packet => {
+                let packet = &mut self.partial_packets[from];
+                packet.push(value);
+                if packet.len() < 3 {
+                    return Ok(());
+                }
+                let (address, x, y) = (packet[0], packet[1], packet[2]);
+                packet.clear();
+
+                if let Some(nat) = &mut self.nat {
+                    if nat.address == address {
+                        nat.last = Some((x, y));
+                        self.events.push_back(ClusterEvent::NatReceived { x, y });
+                        return Ok(());
+                    }
+                }
+                let to = match usize::try_from(address) {
+                    Ok(to) if to < count => to,
+                    _ => return Err(ClusterError::UnknownAddress { from, address }),
+                };
+                self.send(to, x);
+                self.send(to, y);
+                return Ok(());
+            }
+        };
+
+        for to in targets {
+            self.send(to, value);
+        }
+        Ok(())
+    }
+
+    fn turn(&mut self, index: usize) -> Result<(), ClusterError> {
+        let mut fed_idle = false;
+
+        while !self.halted[index] {
+            let action = self.machines[index]
+                .run()
+                .map_err(|e| ClusterError::Machine(index, e))?;
+
+            match action {
+                Action::Output(value) => self.route(index, value)?,
+                Action::NeedInput => match self.idle_input {
+                    Some(value) if !fed_idle => {
+                        self.machines[index].write_input(value);
+                        fed_idle = true;
+                    }
+                    _ => break,
+                },
+                Action::Halt => self.halted[index] = true,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn end_round(&mut self) {
+        if !self.round_active && self.halted.iter().any(|halted| !halted) {
+            match self.nat.as_ref().and_then(|nat| nat.last) {
+                Some((x, y)) => {
+                    self.send(0, x);
+                    self.send(0, y);
+                    self.events.push_back(ClusterEvent::NatSent { x, y });
+                }
+                None => self.events.push_back(ClusterEvent::Idle),
+            }
+        }
+        self.round_active = false;
+    }
+
+    /// Runs the machines until the next `ClusterEvent`.
+    pub fn run(&mut self) -> Result<ClusterEvent, ClusterError> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(event);
+            }
+            if self.halted.iter().all(|halted| *halted) {
+                return Ok(ClusterEvent::AllHalted);
+            }
+
+            let index = self.next_machine;
+            self.turn(index)?;
+
+            self.next_machine = (index + 1) % self.machines.len();
+            if self.next_machine == 0 {
+                self.end_round();
+            }
+        }
+    }
+}
+
+#[test]
+pub fn ring_test() {
+    // Amplifier feedback loop example from 2019 day 7
+    let program = vec![
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+    let machines = [9, 8, 7, 6, 5]
+        .iter()
+        .map(|phase| {
+            let mut machine = IntCodeInterpreter::new(&program);
+            machine.write_input(*phase);
+            machine
+        })
+        .collect();
+
+    let mut cluster = Cluster::new(machines, Topology::Ring).unwrap();
+    cluster.send(0, 0);
+    assert_eq!(cluster.run().unwrap(), ClusterEvent::AllHalted);
+    assert_eq!(cluster.last_output(4), Some(139629729));
+}
+
+#[test]
+pub fn broadcast_test() {
+    // Each machine outputs its first input plus one
+    let program = vec![3, 9, 1001, 9, 1, 9, 4, 9, 99];
+    let machines = (0..3).map(|_| IntCodeInterpreter::new(&program)).collect();
+    let mut cluster = Cluster::new(machines, Topology::Broadcast).unwrap();
+    cluster.send(0, 10);
+
+    assert_eq!(cluster.run().unwrap(), ClusterEvent::AllHalted);
+    assert_eq!(cluster.last_output(0), Some(11));
+    assert_eq!(cluster.last_output(1), Some(12));
+    assert_eq!(cluster.last_output(2), Some(12));
+}
+
+#[test]
+pub fn packet_nat_test() {
+    // Machine 0 sends (3, 4) to the NAT. Every machine then polls for
+    // packets, and forwards (x + 1, y) to the NAT for each one it receives.
+    let program = super::assembler::assemble(
+        "
+                IN -> [addr]
+                JNZ [addr], #poll
+                OUT #255
+                OUT #3
+                OUT #4
+        poll:   IN -> [x]
+                EQ [x], #-1 -> [t]
+                JNZ [t], #poll
+                IN -> [y]
+                ADD [x], #1 -> [x]
+                OUT #255
+                OUT [x]
+                OUT [y]
+                JZ #0, #poll
+        addr:   DATA 0
+        x:      DATA 0
+        y:      DATA 0
+        t:      DATA 0
+        ",
+    )
+    .unwrap()
+    .words;
+
+    let mut cluster = Cluster::network(&program, 2).with_nat(255);
+    assert_eq!(
+        cluster.run().unwrap(),
+        ClusterEvent::NatReceived { x: 3, y: 4 }
+    );
+    assert_eq!(cluster.run().unwrap(), ClusterEvent::NatSent { x: 3, y: 4 });
+    assert_eq!(
+        cluster.run().unwrap(),
+        ClusterEvent::NatReceived { x: 4, y: 4 }
+    );
+    assert_eq!(cluster.run().unwrap(), ClusterEvent::NatSent { x: 4, y: 4 });
+
+    let mut cluster = Cluster::network(&program, 2);
+    assert_eq!(
+        cluster.run().unwrap_err(),
+        ClusterError::UnknownAddress {
+            from: 0,
+            address: 255
+        }
+    );
+}
+
+#[test]
+pub fn idle_test() {
+    let machines = (0..2)
+        .map(|_| IntCodeInterpreter::new(&vec![3, 0, 99]))
+        .collect();
+    let mut cluster = Cluster::new(machines, Topology::Ring).unwrap();
+    assert_eq!(cluster.run().unwrap(), ClusterEvent::Idle);
+    cluster.send(1, 5);
+    assert_eq!(cluster.run().unwrap(), ClusterEvent::Idle);
+    assert!(cluster.is_halted(1));
+    cluster.send(0, 5);
+    assert_eq!(cluster.run().unwrap(), ClusterEvent::AllHalted);
+}
+
+#[test]
+pub fn custom_topology_test() {
+    let machines = || (0..2).map(|_| IntCodeInterpreter::new(&vec![99])).collect();
+    assert_eq!(
+        Cluster::new(machines(), Topology::Custom(vec![vec![1], vec![2]])).err(),
+        Some(ClusterError::InvalidTopology(
+            TopologyError::MissingMachine { from: 1, to: 2 }
+        ))
+    );
+    assert_eq!(
+        Cluster::new(machines(), Topology::Custom(vec![vec![1]])).err(),
+        Some(ClusterError::InvalidTopology(TopologyError::EdgeCount {
+            machines: 2,
+            edges: 1
+        }))
+    );
+    assert!(Cluster::new(machines(), Topology::Custom(vec![vec![1], vec![0]])).is_ok());
+}
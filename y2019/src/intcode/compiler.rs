@@ -0,0 +1,434 @@
+//! Translates instructions into closures with their operand modes and
+//! addresses bound in, so that running them skips decoding altogether.
+//!
+//! Each instruction is compiled the second time it runs, so code that only
+//! runs once never pays for compiling. `run` keeps going through compiled
+//! instructions without coming back to the interpreter until one produces
+//! an action or isn't compiled yet. If the program later writes to
+//! any word an instruction was compiled from, that instruction is handed
+//! back to the interpreter for good, so self-modifying code behaves exactly
+//! as it would without compilation.
+
+use super::memory::{Memory, Words, FLAT_LIMIT};
+use super::{
+    checked, Action, Instruction, InstructionByte, IntCodeInterpreter, IntcodeError,
+    IntcodeErrorKind, Parameter,
+};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+pub type Op =
+    Arc<dyn Fn(&mut IntCodeInterpreter) -> Result<Option<Action>, IntcodeErrorKind> + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    NotSeen,
+    /// Run once, by the interpreter.
+    Seen,
+    Compiled,
+    /// Not compilable, or overwritten since it was compiled.
+    Interpreted,
+}
+
+/// Compiled instructions by address.
+#[derive(Default, Clone)]
+pub struct CompiledCode {
+    slots: Vec<Slot>,
+    /// Whether each word is part of an instruction that has been compiled,
+    /// so that most writes can skip looking for one.
+    covered: Vec<bool>,
+    /// The closure for each compiled slot. Taken out of the interpreter
+    /// while its closures run, as they need the interpreter itself.
+    ops: Vec<Option<Op>>,
+}
+
+impl CompiledCode {
+    /// Hands any compiled instruction that covers `address` back to the
+    /// interpreter.
+    pub fn invalidate(&mut self, address: usize) {
+        if !self.covered.get(address).copied().unwrap_or(false) {
+            return;
+        }
+        for ip in Instruction::covering(address, self.slots.len()) {
+            if self.slots[ip] == Slot::Compiled {
+                self.slots[ip] = Slot::Interpreted;
+            }
+        }
+    }
+
+    /// How many instructions are currently running compiled.
+    pub fn compiled(&self) -> usize {
+        self.count(Slot::Compiled)
+    }
+
+    /// How many instructions have been left to the interpreter.
+    pub fn interpreted(&self) -> usize {
+        self.count(Slot::Interpreted)
+    }
+
+    fn count(&self, slot: Slot) -> usize {
+        self.slots.iter().filter(|&&s| s == slot).count()
+    }
+
+    /// The compiled instruction at `ip` from `ops`, compiling it now if it
+    /// has run before, or `None` if the interpreter should run it.
+    fn prepare<'a>(
+        &mut self,
+        memory: &Memory,
+        ops: &'a mut Vec<Option<Op>>,
+        ip: usize,
+    ) -> Option<&'a Op> {
+        match self.slots.get(ip) {
+            Some(Slot::Compiled) => return ops[ip].as_ref(),
+            Some(Slot::Seen) => {}
+            Some(Slot::Interpreted) => return None,
+            // Code far out in paged memory is left to the interpreter
+            None if ip >= FLAT_LIMIT => return None,
+            Some(Slot::NotSeen) | None => {
+                if ip >= self.slots.len() {
+                    // Most code sits in the program as loaded
+                    let len = (ip + 1).max(memory.len().min(FLAT_LIMIT));
+                    self.slots.resize(len, Slot::NotSeen);
+                    ops.resize(len, None);
+                }
+                self.slots[ip] = Slot::Seen;
+                return None;
+            }
+        }
+
+        match compile(memory, ip) {
+            Some((op, size)) => {
+                if ip + size > self.covered.len() {
+                    self.covered.resize(ip + size, false);
+                }
+                for covered in &mut self.covered[ip..ip + size] {
+                    *covered = true;
+                }
+                self.slots[ip] = Slot::Compiled;
+                ops[ip] = Some(op);
+                ops[ip].as_ref()
+            }
+            None => {
+                self.slots[ip] = Slot::Interpreted;
+                None
+            }
+        }
+    }
+}
+
+impl IntCodeInterpreter {
+    /// Starts or stops running instructions through compiled closures. The
+    /// interpreter still takes over while tracing or profiling, as compiled
+    /// instructions don't record anything. Writes made by the program
+    /// hand overwritten instructions back to the interpreter, but anything
+    /// that changes `memory` directly while compiled code is on must call
+    /// `clear_compiled`.
+    pub fn set_compiled(&mut self, enabled: bool) {
+        self.compiled = if enabled {
+            Some(CompiledCode::default())
+        } else {
+            None
+        };
+    }
+
+    pub fn clear_compiled(&mut self) {
+        if let Some(compiled) = &mut self.compiled {
+            *compiled = CompiledCode::default();
+        }
+    }
+
+    pub fn compiled_code(&self) -> Option<&CompiledCode> {
+        self.compiled.as_ref()
+    }
+
+    /// Runs the instruction at `ip` compiled, or returns `None` if the
+    /// interpreter should run it.
+    pub(super) fn step_compiled(
+        &mut self,
+        ip: usize,
+    ) -> Option<Result<Option<Action>, IntcodeErrorKind>> {
+        if self.trace.is_some() || self.profile.is_some() {
+            return None;
+        }
+        let code = self.compiled.as_mut()?;
+        let mut ops = std::mem::take(&mut code.ops);
+        let result = code.prepare(&self.memory, &mut ops, ip).map(|op| op(self));
+        self.restore_ops(ops);
+        result
+    }
+
+    /// Runs instructions that are already compiled until one produces an
+    /// action or fails, or the next one has to go through `step`, which
+    /// gives `None`.
+    pub(super) fn run_compiled(&mut self) -> Result<Option<Action>, IntcodeError> {
+        if self.trace.is_some() || self.profile.is_some() {
+            return Ok(None);
+        }
+        let ops = match &mut self.compiled {
+            Some(code) if code.slots.get(self.ip) == Some(&Slot::Compiled) => {
+                std::mem::take(&mut code.ops)
+            }
+            _ => return Ok(None),
+        };
+
+        let result = loop {
+            let ip = self.ip;
+            let code = self.compiled.as_ref().unwrap();
+            let op = match (code.slots.get(ip), ops.get(ip)) {
+                (Some(Slot::Compiled), Some(Some(op))) => op,
+                _ => break Ok(None),
+            };
+            match op(self) {
+                Ok(None) => {}
+                Ok(Some(action)) => break Ok(Some(action)),
+                Err(kind) => {
+                    break Err(IntcodeError {
+                        ip,
+                        instruction: self.memory.get(ip),
+                        kind,
+                    })
+                }
+            }
+        };
+        self.restore_ops(ops);
+        result
+    }
+
+    fn restore_ops(&mut self, ops: Vec<Option<Op>>) {
+        if let Some(code) = &mut self.compiled {
+            code.ops = ops;
+        }
+    }
+}
+
+/// An operand with its mode decided and its address, if fixed, converted.
+#[derive(Clone, Copy)]
+enum Operand {
+    Immediate(InstructionByte),
+    Position(usize),
+    Relative(InstructionByte),
+}
+
+impl Operand {
+    fn new(param: Parameter) -> Option<Self> {
+        Some(match param {
+            Parameter::Immediate(value) => Operand::Immediate(value),
+            Parameter::Position(pos) => Operand::Position(usize::try_from(pos).ok()?),
+            Parameter::Relative(offset) => Operand::Relative(offset),
+        })
+    }
+
+    fn target(param: Parameter) -> Option<Self> {
+        match param {
+            Parameter::Immediate(_) => None,
+            _ => Self::new(param),
+        }
+    }
+
+    #[inline]
+    fn load(self, interp: &IntCodeInterpreter) -> Result<InstructionByte, IntcodeErrorKind> {
+        Ok(match self {
+            Operand::Immediate(value) => value,
+            Operand::Position(address) => interp.memory.get(address),
+            Operand::Relative(offset) => {
+                let address = interp.address(checked(interp.relative_base.checked_add(offset))?)?;
+                interp.memory.get(address)
+            }
+        })
+    }
+
+    #[inline]
+    fn store(
+        self,
+        interp: &mut IntCodeInterpreter,
+        value: InstructionByte,
+    ) -> Result<(), IntcodeErrorKind> {
+        let address = match self {
+            Operand::Position(address) => address,
+            Operand::Relative(offset) => {
+                interp.address(checked(interp.relative_base.checked_add(offset))?)?
+            }
+            Operand::Immediate(_) => return Err(IntcodeErrorKind::WriteToImmediate),
+        };
+        interp.store(address, value)
+    }
+}
+
+/// Compiles the instruction at `ip`, along with its size. Instructions that
+/// can't be decoded, or that would fail on every run, are left to the
+/// interpreter so that it reports the error.
+fn compile<M: Words + ?Sized>(memory: &M, ip: usize) -> Option<(Op, usize)> {
+    let (inst, size) = Instruction::decode(memory, ip).ok()?;
+    let next = ip + size;
+    let when = matches!(inst, Instruction::JumpIfTrue(..));
+
+    let op: Op = match inst {
+        Instruction::Add(lhs, rhs, to) => {
+            let (lhs, rhs, to) = (Operand::new(lhs)?, Operand::new(rhs)?, Operand::target(to)?);
+            Arc::new(move |interp| {
+                interp.ip = next;
+                let value = checked(lhs.load(interp)?.checked_add(rhs.load(interp)?))?;
+                to.store(interp, value)?;
+                Ok(None)
+            })
+        }
+        Instruction::Multiply(lhs, rhs, to) => {
+            let (lhs, rhs, to) = (Operand::new(lhs)?, Operand::new(rhs)?, Operand::target(to)?);
+            Arc::new(move |interp| {
+                interp.ip = next;
+                let value = checked(lhs.load(interp)?.checked_mul(rhs.load(interp)?))?;
+                to.store(interp, value)?;
+                Ok(None)
+            })
+        }
+        Instruction::LessThan(lhs, rhs, to) => {
+            let (lhs, rhs, to) = (Operand::new(lhs)?, Operand::new(rhs)?, Operand::target(to)?);
+            Arc::new(move |interp| {
+                interp.ip = next;
+                let less = lhs.load(interp)? < rhs.load(interp)?;
+                to.store(interp, less as InstructionByte)?;
+                Ok(None)
+            })
+        }
+        Instruction::Equals(lhs, rhs, to) => {
+            let (lhs, rhs, to) = (Operand::new(lhs)?, Operand::new(rhs)?, Operand::target(to)?);
+            Arc::new(move |interp| {
+                interp.ip = next;
+                let equal = lhs.load(interp)? == rhs.load(interp)?;
+                to.store(interp, equal as InstructionByte)?;
+                Ok(None)
+            })
+        }
+        Instruction::Input(to) => {
+            let to = Operand::target(to)?;
+            Arc::new(move |interp| match interp.read_input() {
+                Some(value) => {
+                    interp.ip = next;
+                    to.store(interp, value)?;
+                    Ok(None)
+                }
+                None => Ok(Some(Action::NeedInput)),
+            })
+        }
+        Instruction::Output(value) => {
+            let value = Operand::new(value)?;
+            Arc::new(move |interp| {
+                interp.ip = next;
+                Ok(Some(Action::Output(value.load(interp)?)))
+            })
+        }
+        Instruction::JumpIfTrue(value, target) | Instruction::JumpIfFalse(value, target) => {
+            let (value, target) = (Operand::new(value)?, Operand::new(target)?);
+            Arc::new(move |interp| {
+                interp.ip = next;
+                if (value.load(interp)? != 0) == when {
+                    let target = target.load(interp)?;
+                    interp.jump_to(target)?;
+                }
+                Ok(None)
+            })
+        }
+        Instruction::AdjustRelativeBase(delta) => {
+            let delta = Operand::new(delta)?;
+            Arc::new(move |interp| {
+                interp.ip = next;
+                interp.relative_base =
+                    checked(interp.relative_base.checked_add(delta.load(interp)?))?;
+                Ok(None)
+            })
+        }
+        Instruction::Halt => Arc::new(move |interp| {
+            interp.ip = next;
+            Ok(Some(Action::Halt))
+        }),
+    };
+
+    Some((op, size))
+}
+
+#[test]
+pub fn compiled_conformance_test() {
+    use super::conformance::failures;
+    use super::memory::MemoryKind;
+
+    for &kind in [MemoryKind::Flat, MemoryKind::Paged].iter() {
+        let failures = failures(|case| {
+            let mut interp = IntCodeInterpreter::with_memory_kind(&case.program, kind);
+            interp.set_compiled(true);
+            interp
+                .run_to_completion(case.inputs.clone())
+                .map_err(|e| e.kind)
+        });
+        assert!(
+            failures.is_empty(),
+            "{:?} memory:\n{}",
+            kind,
+            failures.join("\n")
+        );
+    }
+}
+
+#[test]
+pub fn self_modifying_test() {
+    // OUT #1; ADD [1], #1 -> [1]; EQ [1], #4 -> [14]; JZ [14], #0; HALT
+    // The ADD rewrites the OUT's operand, which is compiled on the second
+    // pass, so the third pass has to run the OUT through the interpreter to
+    // see the new value
+    let program = vec![104, 1, 1001, 1, 1, 1, 1008, 1, 4, 14, 1006, 14, 0, 99, 0];
+
+    let mut prog = IntCodeInterpreter::new(&program);
+    prog.set_compiled(true);
+    assert_eq!(prog.run_to_completion(vec![]), Ok(vec![1, 2, 3]));
+
+    let code = prog.compiled_code().unwrap();
+    assert_eq!(code.interpreted(), 1);
+    assert_eq!(code.compiled(), 3);
+
+    // Direct changes to memory need the compiled code clearing
+    let mut prog = IntCodeInterpreter::new(&vec![104, 1, 1105, 1, 0]);
+    prog.set_compiled(true);
+    assert_eq!(prog.run().unwrap(), Action::Output(1));
+    assert_eq!(prog.run().unwrap(), Action::Output(1));
+    assert_eq!(prog.compiled_code().unwrap().compiled(), 1);
+    prog.memory[1] = 2;
+    prog.clear_compiled();
+    assert_eq!(prog.run().unwrap(), Action::Output(2));
+}
+
+#[test]
+pub fn interpreted_writes_test() {
+    use super::debugger::{Debugger, Stop};
+
+    // ADD #1, #1 -> [0]; HALT, with every write made while the interpreter
+    // has taken over and nothing has been compiled yet
+    let program = vec![1101, 1, 1, 0, 99];
+
+    let mut traced = IntCodeInterpreter::new(&program);
+    traced.set_compiled(true);
+    traced.set_tracing(true);
+    assert_eq!(traced.run_to_completion(vec![]), Ok(vec![]));
+    assert_eq!(traced.memory.get(0), 2);
+
+    let mut profiled = IntCodeInterpreter::new(&program);
+    profiled.set_compiled(true);
+    profiled.set_profiling(true);
+    assert_eq!(profiled.run_to_completion(vec![]), Ok(vec![]));
+    assert_eq!(profiled.memory.get(0), 2);
+
+    let mut interp = IntCodeInterpreter::new(&program);
+    interp.set_compiled(true);
+    let mut debugger = Debugger::new(interp);
+    assert_eq!(debugger.resume(), Stop::Action(Action::Halt));
+    assert_eq!(debugger.interp.memory.get(0), 2);
+
+    CompiledCode::default().invalidate(0);
+
+    // ADD #99, #0 -> [20000000]; JNZ #1, #20000000, running the HALT far out
+    // in paged memory without compiling slots all the way out to it
+    let program = vec![1101, 99, 0, 20000000, 1105, 1, 20000000];
+    let mut paged =
+        IntCodeInterpreter::with_memory_kind(&program, super::memory::MemoryKind::Paged);
+    paged.set_compiled(true);
+    assert_eq!(paged.run(), Ok(Action::Halt));
+    assert!(paged.compiled_code().unwrap().slots.len() < 1000);
+}
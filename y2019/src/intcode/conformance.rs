@@ -0,0 +1,447 @@
+//! Known-good programs with their expected outputs, for checking any way
+//! of running Intcode against the spec.
+
+use super::{InstructionByte, IntcodeErrorKind, MemoryData};
+
+pub struct Case {
+    pub name: String,
+    pub program: MemoryData,
+    pub inputs: Vec<InstructionByte>,
+    /// Everything output up to the halt, or the error that stopped it.
+    pub expected: Result<Vec<InstructionByte>, IntcodeErrorKind>,
+}
+
+fn case(
+    name: &str,
+    program: &[InstructionByte],
+    inputs: &[InstructionByte],
+    expected: Result<Vec<InstructionByte>, IntcodeErrorKind>,
+) -> Case {
+    Case {
+        name: name.to_string(),
+        program: program.to_vec(),
+        inputs: inputs.to_vec(),
+        expected,
+    }
+}
+
+/// Every case in the suite.
+pub fn cases() -> Vec<Case> {
+    let mut cases = Vec::new();
+    cases.extend(arithmetic_cases());
+    cases.extend(jump_cases());
+    cases.extend(io_cases());
+    cases.extend(day5_cases());
+    cases.extend(misc_cases());
+    cases.extend(error_cases());
+    cases
+}
+
+/// Runs every case through `run` and describes each one that gives the
+/// wrong result.
+pub fn failures<F>(mut run: F) -> Vec<String>
+where
+    F: FnMut(&Case) -> Result<Vec<InstructionByte>, IntcodeErrorKind>,
+{
+    cases()
+        .iter()
+        .filter_map(|case| {
+            let actual = run(case);
+            if actual == case.expected {
+                None
+            } else {
+                Some(format!(
+                    "{} with inputs {:?}: expected {:?}, got {:?}",
+                    case.name, case.inputs, case.expected, actual
+                ))
+            }
+        })
+        .collect()
+}
+
+// Operands for the generated cases live at fixed places: position-mode
+// operands at 20 onwards, and relative-mode ones at 5 onwards from a
+// relative base of 100. Results are written to 30 or [r+10].
+const RELATIVE_BASE: InstructionByte = 100;
+const PROGRAM_SIZE: usize = 111;
+
+fn operand(
+    mode: InstructionByte,
+    index: usize,
+    value: InstructionByte,
+    memory: &mut MemoryData,
+) -> InstructionByte {
+    match mode {
+        0 => {
+            memory[20 + index] = value;
+            20 + index as InstructionByte
+        }
+        1 => value,
+        _ => {
+            memory[RELATIVE_BASE as usize + 5 + index] = value;
+            5 + index as InstructionByte
+        }
+    }
+}
+
+fn destination(mode: InstructionByte) -> InstructionByte {
+    if mode == 0 {
+        30
+    } else {
+        10
+    }
+}
+
+/// ADD, MUL, LT and EQ with inputs in every mode and the result written in
+/// both writable modes, then output from where it was written.
+fn arithmetic_cases() -> Vec<Case> {
+    let mut cases = Vec::new();
+    type Op = fn(InstructionByte, InstructionByte) -> InstructionByte;
+    let ops: [(&str, InstructionByte, Op); 4] = [
+        ("ADD", 1, |a, b| a + b),
+        ("MUL", 2, |a, b| a * b),
+        ("LT", 7, |a, b| (a < b) as InstructionByte),
+        ("EQ", 8, |a, b| (a == b) as InstructionByte),
+    ];
+
+    for (name, opcode, op) in ops.iter() {
+        for &(a, b) in [(7, -3), (-3, 7), (6, 6)].iter() {
+            for mode1 in 0..3 {
+                for mode2 in 0..3 {
+                    for &mode3 in [0, 2].iter() {
+                        let mut program = vec![0; PROGRAM_SIZE];
+                        let p1 = operand(mode1, 0, a, &mut program);
+                        let p2 = operand(mode2, 1, b, &mut program);
+                        let p3 = destination(mode3);
+                        program[..9].copy_from_slice(&[
+                            109,
+                            RELATIVE_BASE,
+                            opcode + mode1 * 100 + mode2 * 1000 + mode3 * 10000,
+                            p1,
+                            p2,
+                            p3,
+                            4 + mode3 * 100,
+                            p3,
+                            99,
+                        ]);
+                        cases.push(case(
+                            &format!("{} {}{}{} ({}, {})", name, mode1, mode2, mode3, a, b),
+                            &program,
+                            &[],
+                            Ok(vec![op(a, b)]),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    cases
+}
+
+/// JNZ and JZ with the value and target in every mode. The jump lands on
+/// `OUT #1`; falling through reaches `OUT #0`.
+fn jump_cases() -> Vec<Case> {
+    let mut cases = Vec::new();
+
+    for &(name, opcode) in [("JNZ", 5), ("JZ", 6)].iter() {
+        for &value in [0, 5, -1].iter() {
+            for mode1 in 0..3 {
+                for mode2 in 0..3 {
+                    let mut program = vec![0; PROGRAM_SIZE];
+                    let p1 = operand(mode1, 0, value, &mut program);
+                    let p2 = operand(mode2, 1, 8, &mut program);
+                    program[..11].copy_from_slice(&[
+                        109,
+                        RELATIVE_BASE,
+                        opcode + mode1 * 100 + mode2 * 1000,
+                        p1,
+                        p2,
+                        104,
+                        0,
+                        99,
+                        104,
+                        1,
+                        99,
+                    ]);
+                    let jumps = (value != 0) == (opcode == 5);
+                    cases.push(case(
+                        &format!("{} {}{} ({})", name, mode1, mode2, value),
+                        &program,
+                        &[],
+                        Ok(vec![jumps as InstructionByte]),
+                    ));
+                }
+            }
+        }
+    }
+
+    cases
+}
+
+/// IN in both writable modes, and OUT and ARB in every mode.
+fn io_cases() -> Vec<Case> {
+    let mut cases = Vec::new();
+
+    for &mode in [0, 2].iter() {
+        let mut program = vec![0; PROGRAM_SIZE];
+        let p = destination(mode);
+        program[..7].copy_from_slice(&[
+            109,
+            RELATIVE_BASE,
+            3 + mode * 100,
+            p,
+            4 + mode * 100,
+            p,
+            99,
+        ]);
+        cases.push(case(
+            &format!("IN {}", mode),
+            &program,
+            &[-42],
+            Ok(vec![-42]),
+        ));
+    }
+
+    for mode in 0..3 {
+        let mut program = vec![0; PROGRAM_SIZE];
+        let p = operand(mode, 0, 13, &mut program);
+        program[..5].copy_from_slice(&[109, RELATIVE_BASE, 4 + mode * 100, p, 99]);
+        cases.push(case(&format!("OUT {}", mode), &program, &[], Ok(vec![13])));
+    }
+
+    // ARB by -2 in each mode, then output [r+7], which is then address 105
+    for mode in 0..3 {
+        let mut program = vec![0; PROGRAM_SIZE];
+        let p = operand(mode, 1, -2, &mut program);
+        program[RELATIVE_BASE as usize + 5] = 77;
+        program[..7].copy_from_slice(&[109, RELATIVE_BASE, 9 + mode * 100, p, 204, 7, 99]);
+        cases.push(case(&format!("ARB {}", mode), &program, &[], Ok(vec![77])));
+    }
+
+    cases
+}
+
+/// The comparison and jump examples from 2019 day 5.
+fn day5_cases() -> Vec<Case> {
+    let mut cases = Vec::new();
+
+    for input in 6..=10 {
+        let equal = Ok(vec![(input == 8) as InstructionByte]);
+        let less = Ok(vec![(input < 8) as InstructionByte]);
+        cases.push(case(
+            "day 5 equal to 8 (position)",
+            &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[input],
+            equal.clone(),
+        ));
+        cases.push(case(
+            "day 5 equal to 8 (immediate)",
+            &[3, 3, 1108, -1, 8, 3, 4, 3, 99],
+            &[input],
+            equal,
+        ));
+        cases.push(case(
+            "day 5 less than 8 (position)",
+            &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[input],
+            less.clone(),
+        ));
+        cases.push(case(
+            "day 5 less than 8 (immediate)",
+            &[3, 3, 1107, -1, 8, 3, 4, 3, 99],
+            &[input],
+            less,
+        ));
+    }
+
+    let jump_position = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+    let jump_immediate = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+    for &input in [0, 1, -5].iter() {
+        let expected = Ok(vec![(input != 0) as InstructionByte]);
+        cases.push(case(
+            "day 5 jump (position)",
+            &jump_position,
+            &[input],
+            expected.clone(),
+        ));
+        cases.push(case(
+            "day 5 jump (immediate)",
+            &jump_immediate,
+            &[input],
+            expected,
+        ));
+    }
+
+    let around_8 = vec![
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+        1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20,
+        1105, 1, 46, 98, 99,
+    ];
+    for &(input, output) in [(7, 999), (8, 1000), (9, 1001)].iter() {
+        cases.push(case(
+            "day 5 compare to 8",
+            &around_8,
+            &[input],
+            Ok(vec![output]),
+        ));
+    }
+
+    cases
+}
+
+fn misc_cases() -> Vec<Case> {
+    let quine = vec![
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ];
+    vec![
+        case("HALT", &[99], &[], Ok(vec![])),
+        case(
+            "day 2 example",
+            &[1, 1, 1, 4, 99, 5, 6, 0, 99],
+            &[],
+            Ok(vec![]),
+        ),
+        case("day 9 quine", &quine, &[], Ok(quine.clone())),
+        case(
+            "day 9 large output",
+            &[104, 1125899906842624, 99],
+            &[],
+            Ok(vec![1125899906842624]),
+        ),
+        case(
+            "day 9 large product",
+            &[1102, 34915192, 34915192, 7, 4, 7, 99, 0],
+            &[],
+            Ok(vec![1219070632396864]),
+        ),
+        // Writes beyond the program extend memory, and unwritten memory is zero
+        case(
+            "write past end",
+            &[1101, 2, 3, 1000, 4, 1000, 4, 999, 99],
+            &[],
+            Ok(vec![5, 0]),
+        ),
+        // Relative writes from ADD and IN land relative to the adjusted base
+        case(
+            "relative writes",
+            &[
+                109, 20, 21101, 4, 5, 3, 203, 4, 109, -10, 204, 14, 204, 13, 99,
+            ],
+            &[6],
+            Ok(vec![6, 9]),
+        ),
+        // A program that overwrites its next instruction runs the new one
+        case(
+            "self-modifying",
+            &[1101, 99, 0, 4, 104, 7, 99],
+            &[],
+            Ok(vec![]),
+        ),
+        case("unused inputs", &[3, 0, 4, 0, 99], &[8, 9], Ok(vec![8])),
+    ]
+}
+
+fn error_cases() -> Vec<Case> {
+    vec![
+        case(
+            "unknown opcode",
+            &[42, 0, 0, 0],
+            &[],
+            Err(IntcodeErrorKind::UnknownOpcode(42)),
+        ),
+        case(
+            "opcode 0",
+            &[0],
+            &[],
+            Err(IntcodeErrorKind::UnknownOpcode(0)),
+        ),
+        case(
+            "bad parameter mode",
+            &[304, 0, 99],
+            &[],
+            Err(IntcodeErrorKind::BadParameterMode(3)),
+        ),
+        case(
+            "add to immediate",
+            &[11101, 1, 2, 3, 99],
+            &[],
+            Err(IntcodeErrorKind::WriteToImmediate),
+        ),
+        case(
+            "input to immediate",
+            &[103, 5, 99],
+            &[1],
+            Err(IntcodeErrorKind::WriteToImmediate),
+        ),
+        case(
+            "negative position",
+            &[4, -1, 99],
+            &[],
+            Err(IntcodeErrorKind::NegativeAddress(-1)),
+        ),
+        case(
+            "negative relative",
+            &[109, -5, 204, 1, 99],
+            &[],
+            Err(IntcodeErrorKind::NegativeAddress(-4)),
+        ),
+        case(
+            "jump past end",
+            &[1105, 1, 100],
+            &[],
+            Err(IntcodeErrorKind::JumpOutOfRange(100)),
+        ),
+        case(
+            "jump negative",
+            &[1106, 0, -3],
+            &[],
+            Err(IntcodeErrorKind::JumpOutOfRange(-3)),
+        ),
+        case(
+            "no input",
+            &[3, 0, 99],
+            &[],
+            Err(IntcodeErrorKind::InputExhausted),
+        ),
+        case(
+            "overflow",
+            &[1101, InstructionByte::MAX, 1, 0, 99],
+            &[],
+            Err(IntcodeErrorKind::Overflow),
+        ),
+        case(
+            "output before error",
+            &[104, 1, 42],
+            &[],
+            Err(IntcodeErrorKind::UnknownOpcode(42)),
+        ),
+    ]
+}
+
+#[test]
+pub fn interpreter_conformance_test() {
+    use super::memory::MemoryKind;
+    use super::IntCodeInterpreter;
+
+    for &kind in [MemoryKind::Flat, MemoryKind::Paged].iter() {
+        for &cached in [false, true].iter() {
+            let failures = failures(|case| {
+                let mut interp = IntCodeInterpreter::with_memory_kind(&case.program, kind);
+                interp.set_decode_cache(cached);
+                interp
+                    .run_to_completion(case.inputs.clone())
+                    .map_err(|e| e.kind)
+            });
+            assert!(
+                failures.is_empty(),
+                "{:?} memory, cache {}:\n{}",
+                kind,
+                cached,
+                failures.join("\n")
+            );
+        }
+    }
+
+    assert!(cases().len() > 300);
+}
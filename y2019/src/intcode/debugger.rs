@@ -0,0 +1,278 @@
+use super::trace::TraceEvent;
+use super::{Action, Instruction, InstructionByte, IntCodeInterpreter, IntcodeError};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// Why the debugger handed control back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stop {
+    Breakpoint(usize),
+    Watchpoint {
+        address: usize,
+        old: InstructionByte,
+        new: InstructionByte,
+    },
+    Action(Action),
+    Error(IntcodeError),
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stop::Breakpoint(ip) => write!(f, "breakpoint at {:04}", ip),
+            Stop::Watchpoint { address, old, new } => {
+                write!(f, "watchpoint [{}] {} -> {}", address, old, new)
+            }
+            Stop::Action(Action::Output(value)) => write!(f, "output {}", value),
+            Stop::Action(Action::NeedInput) => write!(f, "waiting for input"),
+            Stop::Action(Action::Halt) => write!(f, "halted"),
+            Stop::Error(e) => write!(f, "error: {}", e),
+        }
+    }
+}
+
+/// Single-steps an interpreter with tracing enabled, stopping at breakpoints
+/// (instruction addresses) and watchpoints (memory cells that get written).
+pub struct Debugger {
+    pub interp: IntCodeInterpreter,
+    pub breakpoints: BTreeSet<usize>,
+    pub watchpoints: BTreeSet<usize>,
+    last_trace: Vec<TraceEvent>,
+    halted: bool,
+}
+
+impl Debugger {
+    pub fn new(mut interp: IntCodeInterpreter) -> Self {
+        interp.set_tracing(true);
+        Self {
+            interp,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            last_trace: Vec::new(),
+            halted: false,
+        }
+    }
+
+    /// The events recorded by the most recent `step`.
+    pub fn last_trace(&self) -> &[TraceEvent] {
+        &self.last_trace
+    }
+
+    /// Executes one instruction, returning why execution should pause, if it should.
+    pub fn step(&mut self) -> Option<Stop> {
+        if self.halted {
+            self.last_trace.clear();
+            return Some(Stop::Action(Action::Halt));
+        }
+
+        let result = self.interp.execute();
+        self.last_trace = self.interp.take_trace();
+
+        let action = match result {
+            Err(e) => return Some(Stop::Error(e)),
+            Ok(action) => action,
+        };
+
+        let watchpoints = &self.watchpoints;
+        let watched = self.last_trace.iter().find_map(|event| match event {
+            TraceEvent::Write { address, old, new } if watchpoints.contains(address) => {
+                Some(Stop::Watchpoint {
+                    address: *address,
+                    old: *old,
+                    new: *new,
+                })
+            }
+            _ => None,
+        });
+
+        if action == Some(Action::Halt) {
+            self.halted = true;
+        }
+
+        watched.or_else(|| action.map(Stop::Action))
+    }
+
+    /// Runs until a breakpoint, watchpoint, action or error. A breakpoint at
+    /// the current address doesn't stop the first instruction, so calling
+    /// `resume` again after a breakpoint carries on.
+    pub fn resume(&mut self) -> Stop {
+        loop {
+            if let Some(stop) = self.step() {
+                return stop;
+            }
+            if self.breakpoints.contains(&self.interp.ip()) {
+                return Stop::Breakpoint(self.interp.ip());
+            }
+        }
+    }
+
+    fn list(&self, count: usize) -> Vec<String> {
+        let mut lines = Vec::<String>::new();
+        let mut addr = self.interp.ip();
+        for _ in 0..count {
+            match Instruction::decode(&self.interp.memory, addr) {
+                Ok((inst, size)) => {
+                    lines.push(format!("{:04}: {}", addr, inst));
+                    addr += size;
+                }
+                Err(_) => {
+                    let word = self.interp.memory.get(addr);
+                    lines.push(format!("{:04}: DATA {}", addr, word));
+                    addr += 1;
+                }
+            }
+        }
+        lines
+    }
+
+    /// A line-based debugger console. Commands:
+    ///
+    /// - `s [n]`: step `n` (default 1) instructions, printing the trace; a blank line steps once
+    /// - `c`: continue to the next stop
+    /// - `b <addr>` / `w <addr>`: set a breakpoint / watchpoint; `d <addr>` deletes either
+    /// - `i <value>`: queue an input value
+    /// - `x <addr> [n]`: examine `n` memory cells
+    /// - `l [n]`: list `n` instructions from the current address
+    /// - `r`: show registers
+    /// - `q`: quit
+    pub fn interact<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        write!(output, "(intcode) ")?;
+        output.flush()?;
+
+        for line in input.lines() {
+            let line = line?;
+            let mut words = line.split_whitespace();
+            let command = words.next().unwrap_or("s");
+            let args = words
+                .map(|w| w.parse::<InstructionByte>().ok())
+                .collect::<Option<Vec<_>>>();
+            let args = match args {
+                Some(args) => args,
+                None => {
+                    writeln!(output, "bad arguments: {}", line)?;
+                    write!(output, "(intcode) ")?;
+                    output.flush()?;
+                    continue;
+                }
+            };
+            let arg = |n: usize, default: InstructionByte| args.get(n).copied().unwrap_or(default);
+
+            match command {
+                "s" | "step" => {
+                    for _ in 0..arg(0, 1) {
+                        let stop = self.step();
+                        for event in &self.last_trace {
+                            writeln!(output, "{}", event)?;
+                        }
+                        if let Some(stop) = stop {
+                            writeln!(output, "{}", stop)?;
+                            break;
+                        }
+                    }
+                }
+                "c" | "continue" => {
+                    let stop = self.resume();
+                    writeln!(output, "{}", stop)?;
+                }
+                "b" | "break" if !args.is_empty() => {
+                    self.breakpoints.insert(arg(0, 0) as usize);
+                }
+                "w" | "watch" if !args.is_empty() => {
+                    self.watchpoints.insert(arg(0, 0) as usize);
+                }
+                "d" | "delete" if !args.is_empty() => {
+                    let addr = arg(0, 0) as usize;
+                    self.breakpoints.remove(&addr);
+                    self.watchpoints.remove(&addr);
+                }
+                "i" | "input" if !args.is_empty() => {
+                    self.interp.write_input(arg(0, 0));
+                }
+                "x" if !args.is_empty() => {
+                    let start = arg(0, 0).max(0) as usize;
+                    for addr in start..start + arg(1, 1).max(0) as usize {
+                        let word = self.interp.memory.get(addr);
+                        writeln!(output, "[{}] = {}", addr, word)?;
+                    }
+                }
+                "l" | "list" => {
+                    for line in self.list(arg(0, 5).max(0) as usize) {
+                        writeln!(output, "{}", line)?;
+                    }
+                }
+                "r" | "regs" => {
+                    writeln!(
+                        output,
+                        "ip {:04} rb {}",
+                        self.interp.ip(),
+                        self.interp.relative_base()
+                    )?;
+                }
+                "q" | "quit" => return Ok(()),
+                _ => writeln!(output, "unknown command: {}", line)?,
+            }
+
+            write!(output, "(intcode) ")?;
+            output.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+pub fn resume_test() {
+    // Count [20] down from 3 to 0, outputting each value
+    let program = vec![4, 20, 1001, 20, -1, 20, 1005, 20, 0, 99];
+    let mut debugger = Debugger::new(IntCodeInterpreter::new(&program));
+    debugger.interp.memory[20] = 3;
+
+    debugger.breakpoints.insert(6);
+    debugger.watchpoints.insert(20);
+
+    assert_eq!(debugger.resume(), Stop::Action(Action::Output(3)));
+    assert_eq!(
+        debugger.resume(),
+        Stop::Watchpoint {
+            address: 20,
+            old: 3,
+            new: 2
+        }
+    );
+    debugger.watchpoints.clear();
+    assert_eq!(debugger.resume(), Stop::Action(Action::Output(2)));
+    assert_eq!(debugger.resume(), Stop::Breakpoint(6));
+    assert_eq!(debugger.resume(), Stop::Action(Action::Output(1)));
+
+    debugger.breakpoints.clear();
+    assert_eq!(debugger.resume(), Stop::Action(Action::Halt));
+    assert_eq!(debugger.resume(), Stop::Action(Action::Halt));
+}
+
+#[test]
+pub fn interact_test() {
+    let mut debugger = Debugger::new(IntCodeInterpreter::new(&vec![3, 5, 4, 5, 99, 0]));
+    let script = "l 2\ns\ni 42\nw 5\nc\ns\nr\nx 5\nq\ns\n";
+    let mut transcript = Vec::<u8>::new();
+    debugger
+        .interact(io::Cursor::new(script), &mut transcript)
+        .unwrap();
+
+    let transcript = String::from_utf8(transcript).unwrap();
+    assert_eq!(
+        transcript.split("(intcode) ").collect::<Vec<_>>(),
+        [
+            "",
+            "0000: IN -> [5]\n0002: OUT [5]\n",
+            "waiting for input\n",
+            "",
+            "",
+            "watchpoint [5] 0 -> 42\n",
+            "0002: OUT [5]\n      read [5] = 42\noutput 42\n",
+            "ip 0004 rb 0\n",
+            "[5] = 42\n",
+            "",
+        ]
+    );
+}
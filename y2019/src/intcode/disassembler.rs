@@ -0,0 +1,129 @@
+use super::{Instruction, InstructionByte, Parameter};
+use std::collections::HashSet;
+use std::fmt;
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Parameter::Position(pos) => write!(f, "[{}]", pos),
+            Parameter::Immediate(val) => write!(f, "#{}", val),
+            Parameter::Relative(offset) if *offset < 0 => write!(f, "[r{}]", offset),
+            Parameter::Relative(offset) => write!(f, "[r+{}]", offset),
+        }
+    }
+}
+
+impl Instruction {
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Add(..) => "ADD",
+            Instruction::Multiply(..) => "MUL",
+            Instruction::Input(_) => "IN",
+            Instruction::Output(_) => "OUT",
+            Instruction::JumpIfTrue(..) => "JNZ",
+            Instruction::JumpIfFalse(..) => "JZ",
+            Instruction::LessThan(..) => "LT",
+            Instruction::Equals(..) => "EQ",
+            Instruction::AdjustRelativeBase(_) => "ARB",
+            Instruction::Halt => "HALT",
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Add(lhs, rhs, out) => write!(f, "ADD {}, {} -> {}", lhs, rhs, out),
+            Instruction::Multiply(lhs, rhs, out) => write!(f, "MUL {}, {} -> {}", lhs, rhs, out),
+            Instruction::Input(out) => write!(f, "IN -> {}", out),
+            Instruction::Output(val) => write!(f, "OUT {}", val),
+            Instruction::JumpIfTrue(val, target) => write!(f, "JNZ {}, {}", val, target),
+            Instruction::JumpIfFalse(val, target) => write!(f, "JZ {}, {}", val, target),
+            Instruction::LessThan(lhs, rhs, out) => write!(f, "LT {}, {} -> {}", lhs, rhs, out),
+            Instruction::Equals(lhs, rhs, out) => write!(f, "EQ {}, {} -> {}", lhs, rhs, out),
+            Instruction::AdjustRelativeBase(delta) => write!(f, "ARB {}", delta),
+            Instruction::Halt => write!(f, "HALT"),
+        }
+    }
+}
+
+/// Renders a memory image as a listing, one line per instruction, e.g.
+/// `0012: ADD [r+3], #5 -> [100]`. Words that don't decode as a complete
+/// instruction are shown as `DATA` lines.
+pub fn disassemble(memory: &[InstructionByte]) -> String {
+    listing_lines(memory)
+        .into_iter()
+        .map(|(addr, text)| format!("{:04}: {}", addr, text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The (address, text) pairs that make up a listing. An instruction is only
+/// shown as such if it re-encodes to exactly the same words, so that the
+/// listing can be fed back through the assembler.
+pub(crate) fn listing_lines(memory: &[InstructionByte]) -> Vec<(usize, String)> {
+    listing_lines_split_at(memory, &HashSet::new())
+}
+
+/// Like `listing_lines`, but never shows an instruction spanning any of the
+/// addresses in `boundaries`, such as labels, so that each one starts a line.
+pub(crate) fn listing_lines_split_at(
+    memory: &[InstructionByte],
+    boundaries: &HashSet<usize>,
+) -> Vec<(usize, String)> {
+    let mut lines = Vec::<(usize, String)>::new();
+    let mut addr = 0;
+
+    while addr < memory.len() {
+        match Instruction::decode(memory, addr) {
+            Ok((inst, size))
+                if addr + size <= memory.len()
+                    && inst.encode() == memory[addr..addr + size]
+                    && !(addr + 1..addr + size).any(|inner| boundaries.contains(&inner)) =>
+            {
+                lines.push((addr, inst.to_string()));
+                addr += size;
+            }
+            _ => {
+                lines.push((addr, format!("DATA {}", memory[addr])));
+                addr += 1;
+            }
+        }
+    }
+
+    lines
+}
+
+#[test]
+pub fn disassemble_test() {
+    let program = [1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50];
+    assert_eq!(
+        disassemble(&program),
+        [
+            "0000: ADD [9], [10] -> [3]",
+            "0004: MUL [3], [11] -> [0]",
+            "0008: HALT",
+            "0009: DATA 30",
+            "0010: DATA 40",
+            "0011: DATA 50",
+        ]
+        .join("\n")
+    );
+
+    let program = [109, -1, 21101, 5, 3, 3, 1205, 0, 7, 3, 7, 204, 2, 4];
+    assert_eq!(
+        disassemble(&program),
+        [
+            "0000: ARB #-1",
+            "0002: ADD #5, #3 -> [r+3]",
+            "0006: JNZ [r+0], #7",
+            "0009: IN -> [7]",
+            "0011: OUT [r+2]",
+            "0013: DATA 4",
+        ]
+        .join("\n")
+    );
+
+    // Unused mode digits don't survive re-encoding, so they're shown as data
+    assert_eq!(disassemble(&[1104, 7]), "0000: DATA 1104\n0001: DATA 7");
+}
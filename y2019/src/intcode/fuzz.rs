@@ -0,0 +1,234 @@
+//! Runs randomly generated programs to check that the interpreter never
+//! panics, that its behaviour is repeatable and survives a snapshot, and
+//! that the decode cache, compiled code and paged memory behave the same.
+
+use super::limits::{Limits, Outcome as Limited};
+use super::memory::MemoryKind;
+use super::snapshot::Snapshot;
+use super::{
+    Action, InstructionByte, IntCodeInterpreter, IntcodeError, IntcodeErrorKind, MemoryData,
+};
+use std::panic::{self, AssertUnwindSafe};
+
+/// A small xorshift generator, so runs can be replayed from a seed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// A word from anywhere in the range of `InstructionByte`, however wide.
+    pub fn word(&mut self) -> InstructionByte {
+        let mut word: InstructionByte = 0;
+        for _ in 0..std::mem::size_of::<InstructionByte>() / 8 {
+            word = word << 32 << 32 | self.next_u64() as InstructionByte;
+        }
+        word
+    }
+}
+
+/// A random program, mostly made of plausible instructions with small
+/// operands so that they do interesting things, with some wild values mixed
+/// in. Inputs are generated along with it.
+pub fn random_program(rng: &mut Rng) -> (MemoryData, Vec<InstructionByte>) {
+    const OPCODES: [InstructionByte; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+    let len = 4 + rng.below(60) as usize;
+    let program = (0..len)
+        .map(|_| match rng.below(10) {
+            0..=3 => {
+                let opcode = OPCODES[rng.below(OPCODES.len() as u64) as usize];
+                let mut word = opcode;
+                let mut scale = 100;
+                for _ in 0..3 {
+                    // Mostly valid modes, with the occasional bad one
+                    word += (rng.below(7) / 2) as InstructionByte * scale;
+                    scale *= 10;
+                }
+                word
+            }
+            4..=8 => rng.below(len as u64 + 8) as InstructionByte - 4,
+            _ => rng.word(),
+        })
+        .collect();
+    let inputs = (0..rng.below(4))
+        .map(|_| rng.below(20) as InstructionByte - 10)
+        .collect();
+
+    (program, inputs)
+}
+
+/// How a run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub outputs: Vec<InstructionByte>,
+    /// `None` if the budget ran out first.
+    pub end: Option<Result<Action, IntcodeError>>,
+    pub state: Snapshot,
+}
+
+/// Runs `interp` for at most `budget` instructions, stopping at a halt,
+/// a request for input, or an error.
+pub fn run_with_budget(interp: &mut IntCodeInterpreter, budget: usize) -> Outcome {
+    let mut outputs = Vec::new();
+    let mut end = None;
+
+    let mut limits = Limits::new().with_instructions(budget as u64);
+    loop {
+        match interp.run_limited(&mut limits) {
+            Ok(Limited::Action(Action::Output(value))) => outputs.push(value),
+            Ok(Limited::Action(action)) => {
+                end = Some(Ok(action));
+                break;
+            }
+            Ok(Limited::Stopped(_)) => break,
+            Err(e) => {
+                end = Some(Err(e));
+                break;
+            }
+        }
+    }
+
+    Outcome {
+        outputs,
+        end,
+        state: interp.snapshot(),
+    }
+}
+
+/// Another way of running a program: its name, memory, and what to turn on.
+type Variant = (&'static str, MemoryKind, fn(&mut IntCodeInterpreter));
+
+/// Checks one program, returning a description of the first problem found.
+pub fn check(
+    program: &MemoryData,
+    inputs: &[InstructionByte],
+    budget: usize,
+) -> Result<(), String> {
+    let start_with = |kind: MemoryKind| {
+        let mut interp = IntCodeInterpreter::with_memory_kind(program, kind);
+        for input in inputs {
+            interp.write_input(*input);
+        }
+        interp
+    };
+    let start = || start_with(MemoryKind::Flat);
+
+    let run = |interp: &mut IntCodeInterpreter, budget: usize| {
+        panic::catch_unwind(AssertUnwindSafe(|| run_with_budget(interp, budget)))
+            .map_err(|_| "interpreter panicked".to_string())
+    };
+
+    let first = run(&mut start(), budget)?;
+    let second = run(&mut start(), budget)?;
+    if first != second {
+        return Err(format!("not deterministic: {:?} then {:?}", first, second));
+    }
+
+    // Paged memory takes addresses that flat memory refuses, so past one of
+    // those the two can only be checked for panics
+    let too_large = matches!(
+        &first.end,
+        Some(Err(IntcodeError {
+            kind: IntcodeErrorKind::AddressTooLarge(_),
+            ..
+        }))
+    );
+    let variants: [Variant; 5] = [
+        ("decode cache", MemoryKind::Flat, |interp| {
+            interp.set_decode_cache(true)
+        }),
+        ("compiled code", MemoryKind::Flat, |interp| {
+            interp.set_compiled(true)
+        }),
+        (
+            "decode cache with compiled code",
+            MemoryKind::Flat,
+            |interp| {
+                interp.set_decode_cache(true);
+                interp.set_compiled(true);
+            },
+        ),
+        ("compiled code while tracing", MemoryKind::Flat, |interp| {
+            interp.set_compiled(true);
+            interp.set_tracing(true);
+        }),
+        ("paged memory", MemoryKind::Paged, |_| {}),
+    ];
+    for (name, kind, setup) in variants.iter() {
+        let mut interp = start_with(*kind);
+        setup(&mut interp);
+        let mut outcome = run(&mut interp, budget).map_err(|e| format!("{} with {}", e, name))?;
+        if *kind == MemoryKind::Paged && too_large {
+            continue;
+        }
+        outcome.state.kind = first.state.kind;
+        if outcome != first {
+            return Err(format!(
+                "{} differs: {:?} instead of {:?}",
+                name, outcome, first
+            ));
+        }
+    }
+
+    // Stop partway, go through a snapshot, and finish from there
+    let split = budget / 2;
+    let mut original = start();
+    let head = run(&mut original, split)?;
+    if head.end.is_none() {
+        let text = original.snapshot().to_string();
+        let snapshot = text.parse::<Snapshot>()?;
+        let mut restored = IntCodeInterpreter::restore(&snapshot);
+        let tail = run(&mut restored, budget - split)?;
+
+        let mut outputs = head.outputs;
+        outputs.extend(tail.outputs);
+        let resumed = Outcome { outputs, ..tail };
+        if resumed != first {
+            return Err(format!(
+                "snapshot changed behaviour: {:?} instead of {:?}",
+                resumed, first
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks `count` random programs generated from `seed`.
+pub fn fuzz(seed: u64, count: usize, budget: usize) -> Result<(), String> {
+    let mut rng = Rng::new(seed);
+    for i in 0..count {
+        let (program, inputs) = random_program(&mut rng);
+        check(&program, &inputs, budget).map_err(|e| {
+            format!(
+                "program {} from seed {}: {}\nprogram {:?}\ninputs {:?}",
+                i, seed, e, program, inputs
+            )
+        })?;
+    }
+    Ok(())
+}
+
+#[test]
+pub fn fuzz_test() {
+    // Any panic, in any configuration, fails the run
+    fuzz(2019, 5000, 500).unwrap();
+
+    // The budget stops infinite loops
+    let outcome = run_with_budget(&mut IntCodeInterpreter::new(&vec![1105, 1, 0]), 100);
+    assert_eq!(outcome.end, None);
+}
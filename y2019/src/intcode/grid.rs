@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// A cell on a grid. Rows grow downwards, as on screen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pos {
+    pub row: isize,
+    pub col: isize,
+}
+
+impl Pos {
+    pub fn new(row: isize, col: isize) -> Self {
+        Self { row, col }
+    }
+
+    pub fn step(self, direction: Direction) -> Self {
+        let (drow, dcol) = direction.delta();
+        Pos::new(self.row + drow, self.col + dcol)
+    }
+
+    pub fn neighbours(self) -> impl Iterator<Item = Pos> {
+        Direction::ALL.iter().map(move |d| self.step(*d))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// The (row, col) change from taking one step this way.
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Right => (0, 1),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+        }
+    }
+
+    pub fn turn_left(self) -> Self {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Right => Direction::Up,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Down,
+        }
+    }
+
+    pub fn turn_right(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    pub fn reverse(self) -> Self {
+        self.turn_left().turn_left()
+    }
+}
+
+/// A sparse, unbounded grid. Only cells that have been set are stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    pub cells: HashMap<Pos, T>,
+}
+
+impl<T> Default for Grid<T> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    pub fn set(&mut self, pos: Pos, value: T) {
+        self.cells.insert(pos, value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// The top-left and bottom-right corners of the cells that have been set.
+    pub fn bounds(&self) -> Option<(Pos, Pos)> {
+        let rows = self.cells.keys().map(|pos| pos.row);
+        let cols = self.cells.keys().map(|pos| pos.col);
+        Some((
+            Pos::new(rows.clone().min()?, cols.clone().min()?),
+            Pos::new(rows.max()?, cols.max()?),
+        ))
+    }
+
+    fn rows<'a, F, C>(&'a self, cell: F) -> Vec<Vec<C>>
+    where
+        F: Fn(Option<&'a T>) -> C,
+    {
+        match self.bounds() {
+            Some((min, max)) => (min.row..=max.row)
+                .map(|row| {
+                    (min.col..=max.col)
+                        .map(|col| cell(self.get(Pos::new(row, col))))
+                        .collect()
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Draws the grid's bounding box as text, one line per row.
+    pub fn render<F: Fn(Option<&T>) -> char>(&self, cell: F) -> String {
+        self.rows(cell)
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Draws the grid as a plain PBM image, where `black` picks the cells
+    /// that are drawn.
+    pub fn to_pbm<F: Fn(Option<&T>) -> bool>(&self, black: F) -> String {
+        let rows = self.rows(|value| if black(value) { "1" } else { "0" });
+        let mut image = String::new();
+        writeln!(
+            image,
+            "P1\n{} {}",
+            rows.first().map_or(0, Vec::len),
+            rows.len()
+        )
+        .unwrap();
+        for row in rows {
+            writeln!(image, "{}", row.join(" ")).unwrap();
+        }
+        image
+    }
+
+    /// Draws the grid as a plain PPM image with an RGB color per cell.
+    pub fn to_ppm<F: Fn(Option<&T>) -> (u8, u8, u8)>(&self, color: F) -> String {
+        let rows = self.rows(|value| {
+            let (r, g, b) = color(value);
+            format!("{} {} {}", r, g, b)
+        });
+        let mut image = String::new();
+        writeln!(
+            image,
+            "P3\n{} {}\n255",
+            rows.first().map_or(0, Vec::len),
+            rows.len()
+        )
+        .unwrap();
+        for row in rows {
+            writeln!(image, "{}", row.join("  ")).unwrap();
+        }
+        image
+    }
+}
+
+#[test]
+pub fn direction_test() {
+    let mut facing = Direction::Up;
+    for expected in [
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+        Direction::Up,
+    ]
+    .iter()
+    {
+        facing = facing.turn_right();
+        assert_eq!(facing, *expected);
+    }
+    assert_eq!(Direction::Up.turn_left(), Direction::Left);
+    assert_eq!(Direction::Left.reverse(), Direction::Right);
+    assert_eq!(Pos::new(0, 0).step(Direction::Up), Pos::new(-1, 0));
+    assert_eq!(Pos::new(2, 3).neighbours().count(), 4);
+}
+
+#[test]
+pub fn render_test() {
+    let mut grid = Grid::new();
+    assert_eq!(grid.bounds(), None);
+    assert_eq!(grid.render(|_: Option<&bool>| '#'), "");
+
+    grid.set(Pos::new(-1, 0), true);
+    grid.set(Pos::new(0, 2), false);
+    grid.set(Pos::new(1, 1), true);
+    assert_eq!(grid.bounds(), Some((Pos::new(-1, 0), Pos::new(1, 2))));
+
+    let text = grid.render(|cell| match cell {
+        Some(true) => '#',
+        Some(false) => '.',
+        None => ' ',
+    });
+    assert_eq!(text, "#  \n  .\n # ");
+
+    let black = |cell: Option<&bool>| cell == Some(&true);
+    assert_eq!(grid.to_pbm(black), "P1\n3 3\n1 0 0\n0 0 0\n0 1 0\n");
+
+    let color = |cell: Option<&bool>| {
+        if cell.is_some() {
+            (255, 0, 0)
+        } else {
+            (0, 0, 0)
+        }
+    };
+    let ppm = grid.to_ppm(color);
+    assert!(ppm.starts_with("P3\n3 3\n255\n255 0 0  0 0 0  0 0 0\n"));
+}
@@ -0,0 +1,180 @@
+use super::{Action, IntCodeInterpreter, IntcodeError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How many instructions run between looks at the clock and the
+/// cancellation token, as both cost more than an instruction.
+const CHECK_INTERVAL: u32 = 1024;
+
+/// Stops a run from another thread. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Which limit stopped a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Instructions,
+    Deadline,
+    Cancelled,
+}
+
+/// How a limited run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The run ended as `run` would have.
+    Action(Action),
+    /// A limit was hit between instructions. The machine can be resumed.
+    Stopped(Limit),
+}
+
+/// Bounds on a run. An instruction budget is used up as instructions run,
+/// so the same `Limits` passed to several runs covers them all.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    instructions: Option<u64>,
+    deadline: Option<Instant>,
+    cancel: Option<CancelToken>,
+    until_check: u32,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_instructions(mut self, instructions: u64) -> Self {
+        self.instructions = Some(instructions);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// What's left of the instruction budget, if there is one.
+    pub fn instructions_left(&self) -> Option<u64> {
+        self.instructions
+    }
+
+    /// Takes one instruction's worth from the limits, or says which one
+    /// has been hit.
+    fn take(&mut self) -> Option<Limit> {
+        if let Some(left) = &mut self.instructions {
+            if *left == 0 {
+                return Some(Limit::Instructions);
+            }
+            *left -= 1;
+        }
+
+        if self.until_check == 0 {
+            self.until_check = CHECK_INTERVAL;
+            if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+                return Some(Limit::Cancelled);
+            }
+            if self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return Some(Limit::Deadline);
+            }
+        }
+        self.until_check -= 1;
+        None
+    }
+}
+
+impl IntCodeInterpreter {
+    /// Like `run`, but gives up once any of `limits` is hit. The clock and
+    /// cancellation token are looked at when the run starts and then every
+    /// thousand or so instructions.
+    pub fn run_limited(&mut self, limits: &mut Limits) -> Result<Outcome, IntcodeError> {
+        // A fresh run always looks at the clock and token first
+        limits.until_check = 0;
+        loop {
+            if let Some(limit) = limits.take() {
+                return Ok(Outcome::Stopped(limit));
+            }
+            if let Some(action) = self.execute()? {
+                return Ok(Outcome::Action(action));
+            }
+        }
+    }
+}
+
+#[test]
+pub fn limits_test() {
+    use std::thread;
+
+    // An infinite loop counting in [7]: ADD #1, [7] -> [7]; JZ #0, #0
+    let program = vec![101, 1, 7, 7, 1106, 0, 0, 0];
+
+    let mut interp = IntCodeInterpreter::new(&program);
+    let mut limits = Limits::new().with_instructions(100);
+    assert_eq!(
+        interp.run_limited(&mut limits),
+        Ok(Outcome::Stopped(Limit::Instructions))
+    );
+    assert_eq!(interp.memory.get(7), 50);
+    assert_eq!(limits.instructions_left(), Some(0));
+
+    // Stopped machines carry on where they left off
+    let mut limits = Limits::new().with_instructions(10);
+    interp.run_limited(&mut limits).unwrap();
+    assert_eq!(interp.memory.get(7), 55);
+
+    let mut limits = Limits::new().with_deadline(Instant::now());
+    assert_eq!(
+        interp.run_limited(&mut limits),
+        Ok(Outcome::Stopped(Limit::Deadline))
+    );
+
+    let token = CancelToken::new();
+    let mut limits = Limits::new().with_cancel(token.clone());
+    let mut interp = IntCodeInterpreter::new(&program);
+    let handle = thread::spawn(move || interp.run_limited(&mut limits));
+    token.cancel();
+    assert_eq!(
+        handle.join().unwrap(),
+        Ok(Outcome::Stopped(Limit::Cancelled))
+    );
+
+    // Programs that finish in time end as usual
+    let mut interp = IntCodeInterpreter::new(&vec![104, 7, 99]);
+    let mut limits = Limits::new()
+        .with_instructions(5)
+        .with_timeout(Duration::from_secs(60));
+    assert_eq!(
+        interp.run_limited(&mut limits),
+        Ok(Outcome::Action(Action::Output(7)))
+    );
+    assert_eq!(
+        interp.run_limited(&mut limits),
+        Ok(Outcome::Action(Action::Halt))
+    );
+    assert_eq!(limits.instructions_left(), Some(3));
+}
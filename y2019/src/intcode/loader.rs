@@ -0,0 +1,258 @@
+use super::disassembler::listing_lines;
+use super::{InstructionByte, MemoryData};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Marks the compact binary form written by `to_compact`.
+const COMPACT_MAGIC: &[u8] = b"ICB1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    Empty,
+    /// The `index`th comma-separated token isn't an integer.
+    BadToken {
+        index: usize,
+        token: String,
+    },
+    /// The compact form is truncated or has a word that doesn't fit.
+    BadCompact,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Empty => write!(f, "program is empty"),
+            LoadError::BadToken { index, token } => {
+                write!(f, "word {} is not an integer: '{}'", index, token)
+            }
+            LoadError::BadCompact => write!(f, "malformed compact program"),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Parses comma-separated program text. Whitespace, including newlines,
+/// around words is ignored, as is a trailing comma.
+pub fn parse_program(text: &str) -> Result<MemoryData, LoadError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(LoadError::Empty);
+    }
+
+    let mut tokens = text.split(',').map(str::trim).collect::<Vec<_>>();
+    if tokens.last() == Some(&"") {
+        tokens.pop();
+    }
+
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(index, token)| {
+            token
+                .parse::<InstructionByte>()
+                .map_err(|_| LoadError::BadToken {
+                    index,
+                    token: token.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Encodes a program as `ICB1` followed by zigzag LEB128 words, which is
+/// several times smaller than the text form and needs no parsing.
+pub fn to_compact(program: &[InstructionByte]) -> Vec<u8> {
+    let mut bytes = COMPACT_MAGIC.to_vec();
+    for word in program {
+        #[allow(clippy::useless_conversion)] // a no-op with wide-words
+        let word = i128::from(*word);
+        let mut zigzag = (word << 1 ^ word >> 127) as u128;
+        loop {
+            let byte = (zigzag & 0x7f) as u8;
+            zigzag >>= 7;
+            if zigzag == 0 {
+                bytes.push(byte);
+                break;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+    bytes
+}
+
+pub fn is_compact(bytes: &[u8]) -> bool {
+    bytes.starts_with(COMPACT_MAGIC)
+}
+
+pub fn from_compact(bytes: &[u8]) -> Result<MemoryData, LoadError> {
+    if !is_compact(bytes) {
+        return Err(LoadError::BadCompact);
+    }
+
+    let mut program = MemoryData::new();
+    let mut zigzag: u128 = 0;
+    let mut shift = 0;
+    for byte in &bytes[COMPACT_MAGIC.len()..] {
+        if shift >= 128 {
+            return Err(LoadError::BadCompact);
+        }
+        zigzag |= ((byte & 0x7f) as u128) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            let word = ((zigzag >> 1) as i128) ^ -((zigzag & 1) as i128);
+            let word = InstructionByte::try_from(word).map_err(|_| LoadError::BadCompact)?;
+            program.push(word);
+            zigzag = 0;
+            shift = 0;
+        }
+    }
+    if shift != 0 {
+        return Err(LoadError::BadCompact);
+    }
+    if program.is_empty() {
+        return Err(LoadError::Empty);
+    }
+
+    Ok(program)
+}
+
+/// Loads a program from either its text or compact form.
+pub fn load(bytes: &[u8]) -> Result<MemoryData, LoadError> {
+    if is_compact(bytes) {
+        from_compact(bytes)
+    } else {
+        parse_program(&String::from_utf8_lossy(bytes))
+    }
+}
+
+pub fn load_file<P: AsRef<Path>>(path: P) -> io::Result<MemoryData> {
+    load(&fs::read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn save_compact<P: AsRef<Path>>(path: P, program: &[InstructionByte]) -> io::Result<()> {
+    fs::write(path, to_compact(program))
+}
+
+/// Size and shape of a program, from a linear sweep of its words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramStats {
+    pub words: usize,
+    pub min: InstructionByte,
+    pub max: InstructionByte,
+    /// Words that decode as instructions, and how many of each mnemonic.
+    pub instructions: usize,
+    pub mnemonics: BTreeMap<String, usize>,
+    /// Words that don't decode as instructions.
+    pub data_words: usize,
+}
+
+impl ProgramStats {
+    pub fn new(program: &[InstructionByte]) -> Self {
+        let mut stats = ProgramStats {
+            words: program.len(),
+            min: program.iter().copied().min().unwrap_or(0),
+            max: program.iter().copied().max().unwrap_or(0),
+            instructions: 0,
+            mnemonics: BTreeMap::new(),
+            data_words: 0,
+        };
+
+        for (_, text) in listing_lines(program) {
+            let mnemonic = text.split(' ').next().unwrap().to_string();
+            if mnemonic == "DATA" {
+                stats.data_words += 1;
+            } else {
+                stats.instructions += 1;
+                *stats.mnemonics.entry(mnemonic).or_default() += 1;
+            }
+        }
+
+        stats
+    }
+}
+
+impl fmt::Display for ProgramStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} words ({} instructions, {} data), values {}..={}",
+            self.words, self.instructions, self.data_words, self.min, self.max
+        )?;
+        let mnemonics = self
+            .mnemonics
+            .iter()
+            .map(|(mnemonic, count)| format!("{} {}", mnemonic, count))
+            .collect::<Vec<_>>();
+        write!(f, "{}", mnemonics.join(", "))
+    }
+}
+
+#[test]
+pub fn parse_test() {
+    assert_eq!(parse_program("1,0,0,0,99"), Ok(vec![1, 0, 0, 0, 99]));
+    assert_eq!(
+        parse_program(" 1, 0,\n0,0 ,\r\n99,\n"),
+        Ok(vec![1, 0, 0, 0, 99])
+    );
+    assert_eq!(parse_program(" \n"), Err(LoadError::Empty));
+    assert_eq!(
+        parse_program("1,x,3"),
+        Err(LoadError::BadToken {
+            index: 1,
+            token: "x".to_string()
+        })
+    );
+    assert_eq!(
+        parse_program("1,,3"),
+        Err(LoadError::BadToken {
+            index: 1,
+            token: "".to_string()
+        })
+    );
+}
+
+#[test]
+pub fn compact_test() {
+    let program = vec![
+        109,
+        1,
+        204,
+        -1,
+        0,
+        1 << 40,
+        -(1 << 40),
+        InstructionByte::MAX,
+        InstructionByte::MIN,
+    ];
+    let bytes = to_compact(&program);
+    assert!(is_compact(&bytes));
+    assert_eq!(from_compact(&bytes), Ok(program.clone()));
+    assert_eq!(load(&bytes), Ok(program));
+    assert_eq!(load(b"3,0,4,0,99\n"), Ok(vec![3, 0, 4, 0, 99]));
+
+    assert_eq!(
+        from_compact(&bytes[..bytes.len() - 1]),
+        Err(LoadError::BadCompact)
+    );
+    assert_eq!(from_compact(b"ICB1"), Err(LoadError::Empty));
+    assert_eq!(from_compact(b"1,2"), Err(LoadError::BadCompact));
+}
+
+#[test]
+pub fn stats_test() {
+    let stats = ProgramStats::new(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+    assert_eq!(stats.words, 12);
+    assert_eq!(stats.instructions, 3);
+    assert_eq!(stats.data_words, 3);
+    assert_eq!(stats.min, 0);
+    assert_eq!(stats.max, 99);
+    assert_eq!(
+        stats.to_string(),
+        "12 words (3 instructions, 3 data), values 0..=99\nADD 1, HALT 1, MUL 1"
+    );
+}
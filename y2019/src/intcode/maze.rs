@@ -0,0 +1,214 @@
+use super::grid::{Direction, Grid, Pos};
+use super::{Action, InstructionByte, IntCodeInterpreter, IntcodeError};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Wall,
+    Open,
+    /// An open cell the program reports as special, such as the oxygen
+    /// system in 2019 day 15.
+    Target,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MazeError {
+    Machine(IntcodeError),
+    /// The program replied to a move with something other than a status.
+    BadReply(Action),
+    BadStatus(InstructionByte),
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MazeError::Machine(e) => write!(f, "{}", e),
+            MazeError::BadReply(action) => write!(f, "expected a status, got {:?}", action),
+            MazeError::BadStatus(status) => write!(f, "unknown status {}", status),
+        }
+    }
+}
+
+impl Error for MazeError {}
+
+impl From<IntcodeError> for MazeError {
+    fn from(e: IntcodeError) -> Self {
+        MazeError::Machine(e)
+    }
+}
+
+/// The movement commands used by the repair droid in 2019 day 15.
+pub fn droid_command(direction: Direction) -> InstructionByte {
+    match direction {
+        Direction::Up => 1,
+        Direction::Down => 2,
+        Direction::Left => 3,
+        Direction::Right => 4,
+    }
+}
+
+/// A map of everything reachable from the robot's starting cell, which is
+/// at `Pos::default()`.
+#[derive(Debug, Clone)]
+pub struct Maze {
+    pub tiles: Grid<Tile>,
+    pub target: Option<Pos>,
+}
+
+impl Maze {
+    /// Maps the maze by breadth-first search, keeping a copy of the machine
+    /// at each frontier cell and trying every unexplored direction from it.
+    /// `command` gives the input for a move; the program must reply with 0
+    /// for a wall, 1 for a move, or 2 for a move onto the target.
+    pub fn explore<F>(interp: &IntCodeInterpreter, command: F) -> Result<Maze, MazeError>
+    where
+        F: Fn(Direction) -> InstructionByte,
+    {
+        let mut maze = Maze {
+            tiles: Grid::new(),
+            target: None,
+        };
+        maze.tiles.set(Pos::default(), Tile::Open);
+
+        let mut frontier = VecDeque::new();
+        frontier.push_back((Pos::default(), interp.clone()));
+
+        while let Some((pos, machine)) = frontier.pop_front() {
+            for direction in Direction::ALL.iter() {
+                let next = pos.step(*direction);
+                if maze.tiles.get(next).is_some() {
+                    continue;
+                }
+
+                let mut machine = machine.clone();
+                machine.write_input(command(*direction));
+                let tile = match machine.run()? {
+                    Action::Output(0) => Tile::Wall,
+                    Action::Output(1) => Tile::Open,
+                    Action::Output(2) => Tile::Target,
+                    Action::Output(status) => return Err(MazeError::BadStatus(status)),
+                    action => return Err(MazeError::BadReply(action)),
+                };
+
+                maze.tiles.set(next, tile);
+                if tile == Tile::Target {
+                    maze.target = Some(next);
+                }
+                if tile != Tile::Wall {
+                    frontier.push_back((next, machine));
+                }
+            }
+        }
+
+        Ok(maze)
+    }
+
+    pub fn is_open(&self, pos: Pos) -> bool {
+        matches!(self.tiles.get(pos), Some(Tile::Open) | Some(Tile::Target))
+    }
+
+    /// The number of steps from `from` to every reachable open cell.
+    pub fn distances(&self, from: Pos) -> HashMap<Pos, usize> {
+        let mut distances = HashMap::new();
+        if !self.is_open(from) {
+            return distances;
+        }
+
+        let mut queue = VecDeque::new();
+        distances.insert(from, 0);
+        queue.push_back(from);
+
+        while let Some(pos) = queue.pop_front() {
+            let distance = distances[&pos];
+            for next in pos.neighbours() {
+                if self.is_open(next) && !distances.contains_key(&next) {
+                    distances.insert(next, distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    pub fn shortest_path(&self, from: Pos, to: Pos) -> Option<usize> {
+        self.distances(from).get(&to).copied()
+    }
+
+    /// How many steps something spreading from `from` takes to fill every
+    /// reachable open cell.
+    pub fn flood_fill_time(&self, from: Pos) -> usize {
+        self.distances(from).values().copied().max().unwrap_or(0)
+    }
+
+    pub fn render(&self) -> String {
+        self.tiles.render(|tile| match tile {
+            Some(Tile::Wall) => '#',
+            Some(Tile::Open) => '.',
+            Some(Tile::Target) => 'O',
+            None => ' ',
+        })
+    }
+}
+
+#[test]
+pub fn explore_test() {
+    // A droid in a fixed maze, stored row by row with 0 for walls, 1 for
+    // open cells and 2 for the target. [p] is the droid's cell, and the
+    // cell it's asked to move to is read through the relative base.
+    let program = super::assembler::assemble(
+        "
+        loop:   IN -> [cmd]
+                EQ [cmd], #1 -> [t]
+                JNZ [t], #north
+                EQ [cmd], #2 -> [t]
+                JNZ [t], #south
+                EQ [cmd], #3 -> [t]
+                JNZ [t], #west
+                ADD [p], #1 -> [n]
+                JZ #0, #look
+        north:  ADD [p], #-6 -> [n]
+                JZ #0, #look
+        south:  ADD [p], #6 -> [n]
+                JZ #0, #look
+        west:   ADD [p], #-1 -> [n]
+        look:   ADD [n], #maze -> [a]
+                ARB [a]
+                ADD [r+0], #0 -> [cell]
+                MUL [a], #-1 -> [a]
+                ARB [a]
+                OUT [cell]
+                JZ [cell], #loop
+                ADD [n], #0 -> [p]
+                JZ #0, #loop
+        cmd:    DATA 0
+        t:      DATA 0
+        n:      DATA 0
+        a:      DATA 0
+        cell:   DATA 0
+        p:      DATA 7
+        maze:   DATA 0, 0, 0, 0, 0, 0
+                DATA 0, 1, 1, 1, 1, 0
+                DATA 0, 1, 0, 0, 1, 0
+                DATA 0, 1, 1, 2, 1, 0
+                DATA 0, 0, 0, 0, 0, 0
+        ",
+    )
+    .unwrap()
+    .words;
+
+    let maze = Maze::explore(&IntCodeInterpreter::new(&program), droid_command).unwrap();
+    assert_eq!(maze.target, Some(Pos::new(2, 2)));
+    assert_eq!(
+        maze.render(),
+        [" #### ", "#....#", "#.##.#", "#..O.#", " #### "].join("\n")
+    );
+
+    let target = maze.target.unwrap();
+    assert_eq!(maze.shortest_path(Pos::default(), target), Some(4));
+    assert_eq!(maze.shortest_path(Pos::default(), Pos::new(-1, 0)), None);
+    assert_eq!(maze.distances(target).len(), 10);
+    assert_eq!(maze.flood_fill_time(target), 5);
+}
@@ -0,0 +1,223 @@
+use super::{InstructionByte, MemoryData};
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+pub const PAGE_SIZE: usize = 1024;
+
+/// The most words a program may use with flat memory, so that a write to a
+/// wild address fails cleanly instead of trying to allocate all of memory.
+pub const FLAT_LIMIT: usize = 1 << 24;
+
+/// How many zero words `Memory::runs` keeps inside a run rather than
+/// starting a new one.
+const MAX_RUN_GAP: usize = 16;
+
+/// Anything instructions can be decoded from.
+pub trait Words {
+    /// The word at `addr`. Memory that has never been written reads as zero.
+    fn word(&self, addr: usize) -> InstructionByte;
+}
+
+impl Words for [InstructionByte] {
+    fn word(&self, addr: usize) -> InstructionByte {
+        self.get(addr).copied().unwrap_or(0)
+    }
+}
+
+impl Words for Memory {
+    fn word(&self, addr: usize) -> InstructionByte {
+        self.get(addr)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryKind {
+    Flat,
+    Paged,
+}
+
+/// An interpreter's memory, which is conceptually infinite and zero-filled.
+#[derive(Debug, Clone)]
+pub enum Memory {
+    /// A single vector, grown to cover the highest address written.
+    Flat(MemoryData),
+    /// Fixed-size pages allocated on first write, so writing to a far-flung
+    /// address doesn't allocate everything below it. `len` is one past the
+    /// highest address written.
+    Paged {
+        pages: HashMap<usize, Box<[InstructionByte]>>,
+        len: usize,
+    },
+}
+
+impl Memory {
+    pub fn new(kind: MemoryKind, initial: &[InstructionByte]) -> Self {
+        match kind {
+            MemoryKind::Flat => Memory::Flat(initial.to_vec()),
+            MemoryKind::Paged => {
+                let mut memory = Memory::Paged {
+                    pages: HashMap::new(),
+                    len: 0,
+                };
+                for (addr, word) in initial.iter().enumerate() {
+                    memory.set(addr, *word);
+                }
+                memory
+            }
+        }
+    }
+
+    pub fn kind(&self) -> MemoryKind {
+        match self {
+            Memory::Flat(_) => MemoryKind::Flat,
+            Memory::Paged { .. } => MemoryKind::Paged,
+        }
+    }
+
+    /// One past the highest address loaded or written.
+    pub fn len(&self) -> usize {
+        match self {
+            Memory::Flat(words) => words.len(),
+            Memory::Paged { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, addr: usize) -> InstructionByte {
+        self[addr]
+    }
+
+    pub fn set(&mut self, addr: usize, value: InstructionByte) {
+        self[addr] = value;
+    }
+
+    /// The contents from address 0 up to `len`.
+    pub fn to_vec(&self) -> MemoryData {
+        match self {
+            Memory::Flat(words) => words.clone(),
+            Memory::Paged { .. } => (0..self.len()).map(|addr| self[addr]).collect(),
+        }
+    }
+
+    /// The non-zero contents as `(start address, words)` runs in address
+    /// order, with short stretches of zeros kept inside runs. Only words
+    /// that are stored get looked at, so this stays small for paged memory
+    /// written far out.
+    pub fn runs(&self) -> Vec<(usize, MemoryData)> {
+        let stored: Box<dyn Iterator<Item = (usize, InstructionByte)>> = match self {
+            Memory::Flat(words) => Box::new(words.iter().copied().enumerate()),
+            Memory::Paged { pages, .. } => {
+                let mut numbers: Vec<usize> = pages.keys().copied().collect();
+                numbers.sort_unstable();
+                Box::new(numbers.into_iter().flat_map(move |number| {
+                    let start = number * PAGE_SIZE;
+                    pages[&number]
+                        .iter()
+                        .enumerate()
+                        .map(move |(offset, word)| (start + offset, *word))
+                }))
+            }
+        };
+
+        let mut runs: Vec<(usize, MemoryData)> = Vec::new();
+        for (addr, word) in stored.filter(|(_, word)| *word != 0) {
+            match runs.last_mut() {
+                Some((start, run)) if addr - (*start + run.len()) <= MAX_RUN_GAP => {
+                    run.resize(addr - *start, 0);
+                    run.push(word);
+                }
+                _ => runs.push((addr, vec![word])),
+            }
+        }
+        runs
+    }
+
+    /// Memory of the given kind and length holding `runs`; the inverse of
+    /// `runs`.
+    pub fn from_runs(kind: MemoryKind, len: usize, runs: &[(usize, MemoryData)]) -> Self {
+        let mut memory = Memory::new(kind, &[]);
+        for (start, words) in runs {
+            for (offset, word) in words.iter().enumerate() {
+                memory.set(start + offset, *word);
+            }
+        }
+        match &mut memory {
+            Memory::Flat(words) => {
+                if words.len() < len {
+                    words.resize(len, 0);
+                }
+            }
+            Memory::Paged { len: paged_len, .. } => *paged_len = (*paged_len).max(len),
+        }
+        memory
+    }
+}
+
+impl Index<usize> for Memory {
+    type Output = InstructionByte;
+
+    fn index(&self, addr: usize) -> &InstructionByte {
+        match self {
+            Memory::Flat(words) => words.get(addr).unwrap_or(&0),
+            Memory::Paged { pages, .. } => pages
+                .get(&(addr / PAGE_SIZE))
+                .map(|page| &page[addr % PAGE_SIZE])
+                .unwrap_or(&0),
+        }
+    }
+}
+
+/// Writing beyond the current end of memory extends it.
+impl IndexMut<usize> for Memory {
+    fn index_mut(&mut self, addr: usize) -> &mut InstructionByte {
+        match self {
+            Memory::Flat(words) => {
+                if addr >= words.len() {
+                    words.resize(addr + 1, 0);
+                }
+                &mut words[addr]
+            }
+            Memory::Paged { pages, len } => {
+                *len = (*len).max(addr.saturating_add(1));
+                let page = pages
+                    .entry(addr / PAGE_SIZE)
+                    .or_insert_with(|| vec![0; PAGE_SIZE].into_boxed_slice());
+                &mut page[addr % PAGE_SIZE]
+            }
+        }
+    }
+}
+
+#[test]
+pub fn paged_memory_test() {
+    use super::{Action, IntCodeInterpreter};
+
+    // Store 42 at address 10^9, then read it back and output it
+    let program = vec![1101, 40, 2, 1000000000, 4, 1000000000, 99];
+    let mut prog = IntCodeInterpreter::with_memory_kind(&program, MemoryKind::Paged);
+    assert_eq!(prog.run().unwrap(), Action::Output(42));
+    assert_eq!(prog.memory[1000000000], 42);
+    assert_eq!(prog.memory[999999999], 0);
+    assert_eq!(prog.memory.len(), 1000000001);
+    match &prog.memory {
+        Memory::Paged { pages, .. } => assert_eq!(pages.len(), 2),
+        Memory::Flat(_) => unreachable!(),
+    }
+
+    let quine = vec![
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ];
+    for kind in [MemoryKind::Flat, MemoryKind::Paged].iter() {
+        let mut prog = IntCodeInterpreter::with_memory_kind(&quine, *kind);
+        let mut output = Vec::<InstructionByte>::new();
+        while let Action::Output(o) = prog.run().unwrap() {
+            output.push(o);
+        }
+        assert_eq!(output, quine);
+        assert_eq!(prog.memory.kind(), *kind);
+        assert_eq!(prog.memory.len(), 102);
+    }
+}
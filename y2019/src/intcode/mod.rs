@@ -0,0 +1,656 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+pub mod arcade;
+pub mod ascii;
+pub mod assembler;
+pub mod cache;
+pub mod channel;
+pub mod cluster;
+pub mod compiler;
+pub mod conformance;
+pub mod debugger;
+pub mod disassembler;
+pub mod fuzz;
+pub mod grid;
+pub mod limits;
+pub mod loader;
+pub mod maze;
+pub mod memory;
+pub mod profile;
+pub mod robot;
+pub mod search;
+pub mod snapshot;
+pub mod stream;
+pub mod trace;
+
+use cache::DecodeCache;
+use compiler::CompiledCode;
+use memory::{Memory, MemoryKind, Words, FLAT_LIMIT};
+use profile::Profile;
+use trace::TraceEvent;
+
+/// One machine word. Arithmetic on words is checked, so a program that
+/// overflows stops with `IntcodeErrorKind::Overflow` rather than wrapping.
+/// Programs that need more range can be run by building with the
+/// `wide-words` feature, which makes words 128 bits wide.
+#[cfg(not(feature = "wide-words"))]
+pub type InstructionByte = i64;
+#[cfg(feature = "wide-words")]
+pub type InstructionByte = i128;
+pub type InstructionBytes = Vec<InstructionByte>;
+pub type MemoryData = Vec<InstructionByte>;
+
+#[derive(Clone)]
+pub struct IntCodeInterpreter {
+    pub memory: Memory,
+    ip: usize,
+    inputs: MemoryData,
+    relative_base: InstructionByte,
+    trace: Option<Vec<TraceEvent>>,
+    profile: Option<Profile>,
+    decoded: Option<DecodeCache>,
+    compiled: Option<CompiledCode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Output(InstructionByte),
+    /// The program wants input but none is queued. The input instruction
+    /// will be re-executed on the next `run`, after a `write_input`.
+    NeedInput,
+    Halt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntcodeErrorKind {
+    UnknownOpcode(InstructionByte),
+    BadParameterMode(InstructionByte),
+    WriteToImmediate,
+    NegativeAddress(InstructionByte),
+    JumpOutOfRange(InstructionByte),
+    /// An input instruction ran after the input iterator given to
+    /// `outputs` or `run_to_completion` was used up.
+    InputExhausted,
+    /// The program given to `last_output` halted without outputting anything.
+    NoOutput,
+    /// An address too large for the memory backend; flat memory stops at
+    /// `memory::FLAT_LIMIT` words.
+    AddressTooLarge(InstructionByte),
+    /// An arithmetic result, relative base or relative address didn't fit
+    /// in a word.
+    Overflow,
+}
+
+/// A fault raised while executing the instruction at `ip`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntcodeError {
+    pub ip: usize,
+    pub instruction: InstructionByte,
+    pub kind: IntcodeErrorKind,
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (ip {}, instruction {})",
+            self.kind, self.ip, self.instruction
+        )
+    }
+}
+
+impl fmt::Display for IntcodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeErrorKind::UnknownOpcode(opcode) => write!(f, "unknown opcode {}", opcode),
+            IntcodeErrorKind::BadParameterMode(mode) => write!(f, "bad parameter mode {}", mode),
+            IntcodeErrorKind::WriteToImmediate => write!(f, "write to immediate parameter"),
+            IntcodeErrorKind::NegativeAddress(addr) => write!(f, "negative address {}", addr),
+            IntcodeErrorKind::JumpOutOfRange(target) => {
+                write!(f, "jump out of range to {}", target)
+            }
+            IntcodeErrorKind::InputExhausted => write!(f, "input exhausted"),
+            IntcodeErrorKind::NoOutput => write!(f, "halted without output"),
+            IntcodeErrorKind::AddressTooLarge(addr) => write!(f, "address {} too large", addr),
+            IntcodeErrorKind::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl Error for IntcodeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Parameter {
+    Position(InstructionByte),
+    Immediate(InstructionByte),
+    Relative(InstructionByte),
+}
+impl Parameter {
+    pub fn new(
+        mode: InstructionByte,
+        value: InstructionByte,
+    ) -> Result<Parameter, IntcodeErrorKind> {
+        match mode {
+            0 => Ok(Parameter::Position(value)),
+            1 => Ok(Parameter::Immediate(value)),
+            2 => Ok(Parameter::Relative(value)),
+            _ => Err(IntcodeErrorKind::BadParameterMode(mode)),
+        }
+    }
+
+    pub fn mode(&self) -> InstructionByte {
+        match self {
+            Parameter::Position(_) => 0,
+            Parameter::Immediate(_) => 1,
+            Parameter::Relative(_) => 2,
+        }
+    }
+
+    pub fn value(&self) -> InstructionByte {
+        match self {
+            Parameter::Position(val) | Parameter::Immediate(val) | Parameter::Relative(val) => *val,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Add(Parameter, Parameter, Parameter),
+    Multiply(Parameter, Parameter, Parameter),
+    Input(Parameter),
+    Output(Parameter),
+    JumpIfTrue(Parameter, Parameter),
+    JumpIfFalse(Parameter, Parameter),
+    LessThan(Parameter, Parameter, Parameter),
+    Equals(Parameter, Parameter, Parameter),
+    AdjustRelativeBase(Parameter),
+    Halt,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `ip`, returning it along with the number of
+    /// words it occupies. Memory beyond the end of `memory` reads as zero.
+    pub fn decode<M: Words + ?Sized>(
+        memory: &M,
+        ip: usize,
+    ) -> Result<(Instruction, usize), IntcodeErrorKind> {
+        let word = |offset: usize| memory.word(ip + offset);
+        let inst_byte = word(0);
+        let opcode = inst_byte % 100;
+        let mode1 = (inst_byte / 100) % 10;
+        let mode2 = (inst_byte / 1000) % 10;
+        let mode3 = (inst_byte / 10000) % 10;
+
+        let p1 = || Parameter::new(mode1, word(1));
+        let p2 = || Parameter::new(mode2, word(2));
+        let p3 = || Parameter::new(mode3, word(3));
+
+        let inst = match opcode {
+            1 => Instruction::Add(p1()?, p2()?, p3()?),
+            2 => Instruction::Multiply(p1()?, p2()?, p3()?),
+            3 => Instruction::Input(p1()?),
+            4 => Instruction::Output(p1()?),
+            5 => Instruction::JumpIfTrue(p1()?, p2()?),
+            6 => Instruction::JumpIfFalse(p1()?, p2()?),
+            7 => Instruction::LessThan(p1()?, p2()?, p3()?),
+            8 => Instruction::Equals(p1()?, p2()?, p3()?),
+            9 => Instruction::AdjustRelativeBase(p1()?),
+            99 => Instruction::Halt,
+            _ => return Err(IntcodeErrorKind::UnknownOpcode(opcode)),
+        };
+
+        let size = inst.size();
+        Ok((inst, size))
+    }
+
+    /// Number of words occupied by this instruction, including the opcode.
+    pub fn size(&self) -> usize {
+        match self {
+            Instruction::Add(..)
+            | Instruction::Multiply(..)
+            | Instruction::LessThan(..)
+            | Instruction::Equals(..) => 4,
+            Instruction::JumpIfTrue(..) | Instruction::JumpIfFalse(..) => 3,
+            Instruction::Input(..)
+            | Instruction::Output(..)
+            | Instruction::AdjustRelativeBase(..) => 2,
+            Instruction::Halt => 1,
+        }
+    }
+
+    /// The longest instruction, so the furthest back a write can land inside one.
+    pub const MAX_SIZE: usize = 4;
+
+    /// Where an instruction that covers `address` could start, limited to
+    /// addresses below `len`.
+    pub fn covering(address: usize, len: usize) -> Range<usize> {
+        address.saturating_sub(Self::MAX_SIZE - 1)..address.saturating_add(1).min(len)
+    }
+
+    /// Encodes this instruction back into memory words; the inverse of `decode`.
+    pub fn encode(&self) -> InstructionBytes {
+        let (opcode, params): (InstructionByte, Vec<&Parameter>) = match self {
+            Instruction::Add(a, b, c) => (1, vec![a, b, c]),
+            Instruction::Multiply(a, b, c) => (2, vec![a, b, c]),
+            Instruction::Input(a) => (3, vec![a]),
+            Instruction::Output(a) => (4, vec![a]),
+            Instruction::JumpIfTrue(a, b) => (5, vec![a, b]),
+            Instruction::JumpIfFalse(a, b) => (6, vec![a, b]),
+            Instruction::LessThan(a, b, c) => (7, vec![a, b, c]),
+            Instruction::Equals(a, b, c) => (8, vec![a, b, c]),
+            Instruction::AdjustRelativeBase(a) => (9, vec![a]),
+            Instruction::Halt => (99, vec![]),
+        };
+
+        let mut inst_byte = opcode;
+        let mut scale = 100;
+        for param in &params {
+            inst_byte += param.mode() * scale;
+            scale *= 10;
+        }
+
+        let mut words = vec![inst_byte];
+        words.extend(params.iter().map(|param| param.value()));
+        words
+    }
+}
+
+fn checked(result: Option<InstructionByte>) -> Result<InstructionByte, IntcodeErrorKind> {
+    result.ok_or(IntcodeErrorKind::Overflow)
+}
+
+impl IntCodeInterpreter {
+    pub fn new(instructions: &MemoryData) -> Self {
+        Self::with_memory_kind(instructions, MemoryKind::Flat)
+    }
+
+    pub fn with_memory_kind(instructions: &MemoryData, kind: MemoryKind) -> Self {
+        Self {
+            ip: 0,
+            memory: Memory::new(kind, instructions),
+            inputs: Vec::new(),
+            relative_base: 0,
+            trace: None,
+            profile: None,
+            decoded: None,
+            compiled: None,
+        }
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn relative_base(&self) -> InstructionByte {
+        self.relative_base
+    }
+
+    /// Starts or stops recording a `TraceEvent` for every decoded instruction,
+    /// operand read, memory write and relative base change.
+    pub fn set_tracing(&mut self, enabled: bool) {
+        self.trace = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Returns the events recorded since the last call.
+    pub fn take_trace(&mut self) -> Vec<TraceEvent> {
+        self.trace.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Starts or stops counting executions per address, the opcode mix, and
+    /// memory reads and writes per address.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profile = if enabled {
+            Some(Profile::default())
+        } else {
+            None
+        };
+    }
+
+    /// Returns the counts gathered since the last call.
+    pub fn take_profile(&mut self) -> Profile {
+        self.profile
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn record(&mut self, event: TraceEvent) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(event);
+        }
+    }
+
+    /// Starts or stops caching decoded instructions. Writes made by the
+    /// program invalidate the cache, but anything that changes `memory`
+    /// directly while the cache is on must call `clear_decode_cache`.
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.decoded = if enabled {
+            Some(DecodeCache::default())
+        } else {
+            None
+        };
+    }
+
+    pub fn clear_decode_cache(&mut self) {
+        if let Some(decoded) = &mut self.decoded {
+            decoded.clear();
+        }
+    }
+
+    fn next(&mut self) -> Result<Instruction, IntcodeErrorKind> {
+        let (inst, len) = match &mut self.decoded {
+            Some(decoded) => match decoded.get(self.ip) {
+                Some(entry) => entry.clone(),
+                None => {
+                    let (inst, len) = Instruction::decode(&self.memory, self.ip)?;
+                    decoded.insert(self.ip, inst.clone(), len);
+                    (inst, len)
+                }
+            },
+            None => Instruction::decode(&self.memory, self.ip)?,
+        };
+        self.ip += len;
+        Ok(inst)
+    }
+
+    fn address(&self, pos: InstructionByte) -> Result<usize, IntcodeErrorKind> {
+        if pos < 0 {
+            Err(IntcodeErrorKind::NegativeAddress(pos))
+        } else {
+            // One past the address has to fit too, as memory's length
+            usize::try_from(pos)
+                .ok()
+                .filter(|&address| address < usize::MAX)
+                .ok_or(IntcodeErrorKind::AddressTooLarge(pos))
+        }
+    }
+
+    fn get_parameter(&mut self, parameter: Parameter) -> Result<InstructionByte, IntcodeErrorKind> {
+        let final_pos = match parameter {
+            Parameter::Immediate(value) => {
+                self.record(TraceEvent::Read {
+                    address: None,
+                    value,
+                });
+                return Ok(value);
+            }
+            Parameter::Position(pos) => self.address(pos)?,
+            Parameter::Relative(pos) => {
+                self.address(checked(self.relative_base.checked_add(pos))?)?
+            }
+        };
+
+        if let Some(profile) = &mut self.profile {
+            profile.count_read(final_pos);
+        }
+        let value = self.memory.get(final_pos);
+        self.record(TraceEvent::Read {
+            address: Some(final_pos),
+            value,
+        });
+        Ok(value)
+    }
+
+    fn write(&mut self, to: Parameter, value: InstructionByte) -> Result<(), IntcodeErrorKind> {
+        let final_pos = match to {
+            Parameter::Position(pos) => self.address(pos)?,
+            Parameter::Immediate(_) => return Err(IntcodeErrorKind::WriteToImmediate),
+            Parameter::Relative(pos) => {
+                self.address(checked(self.relative_base.checked_add(pos))?)?
+            }
+        };
+        self.store(final_pos, value)
+    }
+
+    fn store(&mut self, final_pos: usize, value: InstructionByte) -> Result<(), IntcodeErrorKind> {
+        if self.memory.kind() == MemoryKind::Flat && final_pos >= FLAT_LIMIT {
+            return Err(IntcodeErrorKind::AddressTooLarge(
+                final_pos as InstructionByte,
+            ));
+        }
+        if self.trace.is_some() {
+            self.record(TraceEvent::Write {
+                address: final_pos,
+                old: self.memory.get(final_pos),
+                new: value,
+            });
+        }
+        if let Some(profile) = &mut self.profile {
+            profile.count_write(final_pos);
+        }
+        if let Some(decoded) = &mut self.decoded {
+            decoded.invalidate(final_pos);
+        }
+        if let Some(compiled) = &mut self.compiled {
+            compiled.invalidate(final_pos);
+        }
+        self.memory.set(final_pos, value);
+        Ok(())
+    }
+
+    fn jump(&mut self, target: Parameter) -> Result<(), IntcodeErrorKind> {
+        let target = self.get_parameter(target)?;
+        self.jump_to(target)
+    }
+
+    fn jump_to(&mut self, target: InstructionByte) -> Result<(), IntcodeErrorKind> {
+        match usize::try_from(target) {
+            Ok(target) if target < self.memory.len() => {
+                self.ip = target;
+                Ok(())
+            }
+            _ => Err(IntcodeErrorKind::JumpOutOfRange(target)),
+        }
+    }
+
+    fn read_input(&mut self) -> Option<InstructionByte> {
+        if !self.inputs.is_empty() {
+            Some(self.inputs.remove(0))
+        } else {
+            None
+        }
+    }
+
+    pub fn write_input(&mut self, input: InstructionByte) {
+        self.inputs.push(input);
+    }
+
+    pub fn execute(&mut self) -> Result<Option<Action>, IntcodeError> {
+        let inst_ip = self.ip;
+        let inst_byte = self.memory.get(inst_ip);
+
+        self.step(inst_ip).map_err(|kind| IntcodeError {
+            ip: inst_ip,
+            instruction: inst_byte,
+            kind,
+        })
+    }
+
+    fn step(&mut self, inst_ip: usize) -> Result<Option<Action>, IntcodeErrorKind> {
+        if let Some(result) = self.step_compiled(inst_ip) {
+            return result;
+        }
+
+        let inst = self.next()?;
+        let mut result = None;
+
+        // An IN with nothing to read rewinds to run again later, so it's only
+        // traced and counted once it does
+        let waiting = matches!(inst, Instruction::Input(_)) && self.inputs.is_empty();
+        if self.trace.is_some() && !waiting {
+            self.record(TraceEvent::Instruction {
+                ip: inst_ip,
+                instruction: inst.clone(),
+            });
+        }
+        if let Some(profile) = &mut self.profile {
+            if !waiting {
+                profile.count_instruction(inst_ip, &inst);
+            }
+        }
+
+        match inst {
+            Instruction::Add(lhs, rhs, output) => {
+                let val = checked(
+                    self.get_parameter(lhs)?
+                        .checked_add(self.get_parameter(rhs)?),
+                )?;
+                self.write(output, val)?;
+            }
+            Instruction::Multiply(lhs, rhs, output) => {
+                let val = checked(
+                    self.get_parameter(lhs)?
+                        .checked_mul(self.get_parameter(rhs)?),
+                )?;
+                self.write(output, val)?;
+            }
+            Instruction::Input(pos) => {
+                let val = self.read_input();
+                if let Some(value) = val {
+                    self.write(pos, value)?;
+                } else {
+                    // Rewind so the input instruction is retried on resume
+                    self.ip = inst_ip;
+                    result = Some(Action::NeedInput);
+                }
+            }
+            Instruction::Output(pos) => {
+                let value = self.get_parameter(pos)?;
+                result = Some(Action::Output(value))
+            }
+            Instruction::JumpIfTrue(value, target) => {
+                let value = self.get_parameter(value)?;
+                if value != 0 {
+                    self.jump(target)?;
+                }
+            }
+            Instruction::JumpIfFalse(value, target) => {
+                let value = self.get_parameter(value)?;
+                if value == 0 {
+                    self.jump(target)?;
+                }
+            }
+            Instruction::LessThan(op1, op2, target) => {
+                let value1 = self.get_parameter(op1)?;
+                let value2 = self.get_parameter(op2)?;
+                self.write(target, if value1 < value2 { 1 } else { 0 })?;
+            }
+            Instruction::Equals(op1, op2, target) => {
+                let value1 = self.get_parameter(op1)?;
+                let value2 = self.get_parameter(op2)?;
+                self.write(target, if value1 == value2 { 1 } else { 0 })?;
+            }
+            Instruction::AdjustRelativeBase(delta) => {
+                let value = self.get_parameter(delta)?;
+                let old = self.relative_base;
+                self.relative_base = checked(self.relative_base.checked_add(value))?;
+                self.record(TraceEvent::RelativeBase {
+                    old,
+                    new: self.relative_base,
+                });
+            }
+
+            Instruction::Halt => {
+                result = Some(Action::Halt);
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn run(&mut self) -> Result<Action, IntcodeError> {
+        loop {
+            if let Some(action) = self.run_compiled()? {
+                return Ok(action);
+            }
+            let action_option = self.execute()?;
+
+            if let Some(action) = action_option {
+                return Ok(action);
+            }
+        }
+    }
+}
+
+#[test]
+pub fn error_tests() {
+    fn run(program: &[InstructionByte]) -> IntcodeErrorKind {
+        let mut prog = IntCodeInterpreter::new(&program.to_vec());
+        prog.run().unwrap_err().kind
+    }
+
+    assert_eq!(run(&[42, 0, 0, 0]), IntcodeErrorKind::UnknownOpcode(42));
+    assert_eq!(run(&[304, 0]), IntcodeErrorKind::BadParameterMode(3));
+    assert_eq!(
+        run(&[11101, 1, 2, 3, 99]),
+        IntcodeErrorKind::WriteToImmediate
+    );
+    assert_eq!(run(&[4, -1, 99]), IntcodeErrorKind::NegativeAddress(-1));
+    assert_eq!(
+        run(&[109, -5, 204, 1, 99]),
+        IntcodeErrorKind::NegativeAddress(-4)
+    );
+    assert_eq!(run(&[1105, 1, 100]), IntcodeErrorKind::JumpOutOfRange(100));
+    assert_eq!(
+        run(&[1101, 1, 1, 1 << 40, 99]),
+        IntcodeErrorKind::AddressTooLarge(1 << 40)
+    );
+
+    let mut prog = IntCodeInterpreter::new(&vec![1, 0, 0, 0, 1106, 0, -3]);
+    let err = prog.run().unwrap_err();
+    assert_eq!(err.ip, 4);
+    assert_eq!(err.instruction, 1106);
+    assert_eq!(err.kind, IntcodeErrorKind::JumpOutOfRange(-3));
+
+    // Only reachable with words wider than addresses
+    if let Some(far) = (1 as InstructionByte).checked_shl(64) {
+        assert_eq!(
+            run(&[1105, 1, far + 3, 104, 7, 99]),
+            IntcodeErrorKind::JumpOutOfRange(far + 3)
+        );
+    }
+    if let Ok(top) = InstructionByte::try_from(usize::MAX) {
+        let mut prog =
+            IntCodeInterpreter::with_memory_kind(&vec![1101, 1, 1, top, 99], MemoryKind::Paged);
+        assert_eq!(
+            prog.run().unwrap_err().kind,
+            IntcodeErrorKind::AddressTooLarge(top)
+        );
+    }
+}
+
+#[test]
+pub fn overflow_test() {
+    fn run(program: &[InstructionByte]) -> Result<Action, IntcodeErrorKind> {
+        let mut prog = IntCodeInterpreter::new(&program.to_vec());
+        prog.run().map_err(|e| e.kind)
+    }
+
+    // Only overflows with 64-bit words
+    let big: InstructionByte = 1 << 62;
+    match big.checked_mul(4) {
+        Some(product) => assert_eq!(
+            run(&[1102, big, 4, 7, 4, 7, 99, 0]),
+            Ok(Action::Output(product))
+        ),
+        None => assert_eq!(
+            run(&[1102, big, 4, 7, 4, 7, 99, 0]),
+            Err(IntcodeErrorKind::Overflow)
+        ),
+    }
+
+    let max = InstructionByte::MAX;
+    assert_eq!(run(&[1101, max, 1, 0, 99]), Err(IntcodeErrorKind::Overflow));
+    assert_eq!(run(&[1101, max, 0, 0, 4, 0, 99]), Ok(Action::Output(max)));
+    assert_eq!(
+        run(&[109, max, 109, 1, 99]),
+        Err(IntcodeErrorKind::Overflow)
+    );
+    assert_eq!(
+        run(&[109, max, 204, 1, 99]),
+        Err(IntcodeErrorKind::Overflow)
+    );
+}
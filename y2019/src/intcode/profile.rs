@@ -0,0 +1,162 @@
+use super::disassembler::listing_lines;
+use super::{Instruction, InstructionByte};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Execution counts gathered while profiling is enabled.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// How many times the instruction at each address ran.
+    pub executions: BTreeMap<usize, u64>,
+    /// How many times each mnemonic ran.
+    pub opcodes: BTreeMap<&'static str, u64>,
+    /// How many times each address was read or written by an operand.
+    /// Instruction fetches aren't counted.
+    pub reads: BTreeMap<usize, u64>,
+    pub writes: BTreeMap<usize, u64>,
+}
+
+fn hottest(counts: &BTreeMap<usize, u64>, n: usize) -> Vec<(usize, u64)> {
+    let mut counts = counts.iter().map(|(a, c)| (*a, *c)).collect::<Vec<_>>();
+    counts.sort_by(|(a1, c1), (a2, c2)| c2.cmp(c1).then(a1.cmp(a2)));
+    counts.truncate(n);
+    counts
+}
+
+impl Profile {
+    pub(super) fn count_instruction(&mut self, ip: usize, instruction: &Instruction) {
+        *self.executions.entry(ip).or_default() += 1;
+        *self.opcodes.entry(instruction.mnemonic()).or_default() += 1;
+    }
+
+    pub(super) fn count_read(&mut self, address: usize) {
+        *self.reads.entry(address).or_default() += 1;
+    }
+
+    pub(super) fn count_write(&mut self, address: usize) {
+        *self.writes.entry(address).or_default() += 1;
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.executions.values().sum()
+    }
+
+    /// The `n` most executed instruction addresses, busiest first.
+    pub fn hot_spots(&self, n: usize) -> Vec<(usize, u64)> {
+        hottest(&self.executions, n)
+    }
+
+    /// A summary of the opcode mix and the busiest instructions and memory
+    /// cells, showing at most `n` of each.
+    pub fn report(&self, n: usize) -> String {
+        let total = self.instructions();
+        let mut report = String::new();
+        writeln!(
+            report,
+            "{} instructions executed at {} addresses",
+            total,
+            self.executions.len()
+        )
+        .unwrap();
+
+        let mut opcodes = self.opcodes.iter().collect::<Vec<_>>();
+        opcodes.sort_by(|(m1, c1), (m2, c2)| c2.cmp(c1).then(m1.cmp(m2)));
+        writeln!(report, "\nopcode mix:").unwrap();
+        for (mnemonic, count) in opcodes {
+            let percent = *count as f64 * 100.0 / total as f64;
+            writeln!(report, "  {:<4} {:>10} {:>5.1}%", mnemonic, count, percent).unwrap();
+        }
+
+        for (title, counts) in [
+            ("hot instructions", &self.executions),
+            ("memory reads", &self.reads),
+            ("memory writes", &self.writes),
+        ]
+        .iter()
+        {
+            writeln!(report, "\n{}:", title).unwrap();
+            for (address, count) in hottest(counts, n) {
+                writeln!(report, "  {:04} {:>10}", address, count).unwrap();
+            }
+        }
+
+        report
+    }
+
+    /// A listing of `memory` with each line prefixed by how many times it
+    /// ran, or `-` for instructions that never ran. Data that ran, which
+    /// happens when a program modifies itself, is marked with `!`.
+    pub fn coverage(&self, memory: &[InstructionByte]) -> String {
+        listing_lines(memory)
+            .into_iter()
+            .map(|(addr, text)| {
+                let count = match self.executions.get(&addr) {
+                    Some(count) if text.starts_with("DATA") => format!("!{}", count),
+                    Some(count) => count.to_string(),
+                    None if text.starts_with("DATA") => String::new(),
+                    None => "-".to_string(),
+                };
+                format!("{:>10}  {:04}: {}", count, addr, text)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[test]
+pub fn profile_test() {
+    use super::IntCodeInterpreter;
+
+    // Counts [n] down from 3, skipping the OUT on the last pass
+    let program = super::assembler::assemble(
+        "
+        loop:   ADD [n], #-1 -> [n]
+                JZ [n], #end
+                OUT [n]
+                JZ #0, #loop
+        end:    HALT
+                OUT #1
+        n:      DATA 3
+        ",
+    )
+    .unwrap()
+    .words;
+
+    let mut prog = IntCodeInterpreter::new(&program);
+    prog.set_profiling(true);
+    let outputs = prog.run_to_completion(vec![]).unwrap();
+    assert_eq!(outputs, vec![2, 1]);
+
+    let profile = prog.take_profile();
+    assert_eq!(profile.instructions(), 11);
+    assert_eq!(profile.hot_spots(2), vec![(0, 3), (4, 3)]);
+    assert_eq!(profile.opcodes["JZ"], 5);
+    assert_eq!(profile.opcodes["HALT"], 1);
+    assert_eq!(profile.reads[&15], 8);
+    assert_eq!(profile.writes[&15], 3);
+    assert_eq!(prog.take_profile(), Profile::default());
+
+    let coverage = profile.coverage(&program);
+    let lines = coverage.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "         3  0000: ADD [15], #-1 -> [15]");
+    assert_eq!(lines[4], "         1  0012: HALT");
+    assert_eq!(lines[5], "         -  0013: OUT #1");
+    assert_eq!(lines[6], "            0015: DATA 3");
+
+    let report = profile.report(1);
+    assert!(report.starts_with("11 instructions executed at 5 addresses\n"));
+    assert!(report.contains("  JZ            5  45.5%\n"));
+    assert!(report.contains("memory writes:\n  0015          3\n"));
+
+    // An IN only counts once it has input to read
+    let mut prog = IntCodeInterpreter::new(&vec![3, 0, 99]);
+    prog.set_profiling(true);
+    prog.run().unwrap();
+    prog.run().unwrap();
+    prog.write_input(5);
+    prog.run().unwrap();
+    let profile = prog.take_profile();
+    assert_eq!(profile.instructions(), 2);
+    assert_eq!(profile.opcodes["IN"], 1);
+    assert_eq!(profile.hot_spots(1), vec![(0, 1)]);
+}
@@ -0,0 +1,193 @@
+use super::grid::{Direction, Grid, Pos};
+use super::{Action, InstructionByte, IntCodeInterpreter, IntcodeError};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+/// Where a robot is and which way it's facing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Robot {
+    pub pos: Pos,
+    pub facing: Direction,
+}
+
+impl Default for Robot {
+    fn default() -> Self {
+        Self {
+            pos: Pos::default(),
+            facing: Direction::Up,
+        }
+    }
+}
+
+/// What a robot has seen and done.
+#[derive(Debug, Default, Clone)]
+pub struct World {
+    /// The color of every cell the robot has painted.
+    pub painted: Grid<InstructionByte>,
+    /// Every cell the robot has stood on, including where it started.
+    pub visited: HashSet<Pos>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RobotError {
+    Machine(IntcodeError),
+    /// The program output something the protocol doesn't understand.
+    BadOutput(InstructionByte),
+}
+
+impl fmt::Display for RobotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RobotError::Machine(e) => write!(f, "{}", e),
+            RobotError::BadOutput(value) => write!(f, "unexpected robot output {}", value),
+        }
+    }
+}
+
+impl Error for RobotError {}
+
+impl From<IntcodeError> for RobotError {
+    fn from(e: IntcodeError) -> Self {
+        RobotError::Machine(e)
+    }
+}
+
+/// How a particular puzzle's program talks to its robot.
+pub trait Protocol {
+    /// How many outputs make up one command.
+    fn outputs_per_command(&self) -> usize;
+
+    /// The next input for the program when it asks for one, or `None` to
+    /// stop the robot.
+    fn input(&mut self, robot: &Robot, world: &World) -> Option<InstructionByte>;
+
+    /// Carries out one command.
+    fn command(
+        &mut self,
+        outputs: &[InstructionByte],
+        robot: &mut Robot,
+        world: &mut World,
+    ) -> Result<(), RobotError>;
+}
+
+/// The hull painting robot from 2019 day 11. The camera reports the color
+/// underneath, and each command is a color to paint followed by a turn
+/// (0 for left, 1 for right) before moving forward one cell.
+pub struct PaintingProtocol;
+
+impl Protocol for PaintingProtocol {
+    fn outputs_per_command(&self) -> usize {
+        2
+    }
+
+    fn input(&mut self, robot: &Robot, world: &World) -> Option<InstructionByte> {
+        Some(*world.painted.get(robot.pos).unwrap_or(&0))
+    }
+
+    fn command(
+        &mut self,
+        outputs: &[InstructionByte],
+        robot: &mut Robot,
+        world: &mut World,
+    ) -> Result<(), RobotError> {
+        world.painted.set(robot.pos, outputs[0]);
+        robot.facing = match outputs[1] {
+            0 => robot.facing.turn_left(),
+            1 => robot.facing.turn_right(),
+            turn => return Err(RobotError::BadOutput(turn)),
+        };
+        robot.pos = robot.pos.step(robot.facing);
+        world.visited.insert(robot.pos);
+        Ok(())
+    }
+}
+
+/// Drives a robot with `interp` until the program halts or the protocol
+/// stops supplying input.
+pub fn run_robot<P: Protocol>(
+    interp: &mut IntCodeInterpreter,
+    protocol: &mut P,
+    robot: &mut Robot,
+    world: &mut World,
+) -> Result<(), RobotError> {
+    let mut outputs = Vec::new();
+    world.visited.insert(robot.pos);
+
+    loop {
+        match interp.run()? {
+            Action::Output(value) => {
+                outputs.push(value);
+                if outputs.len() == protocol.outputs_per_command() {
+                    protocol.command(&outputs, robot, world)?;
+                    outputs.clear();
+                }
+            }
+            Action::NeedInput => match protocol.input(robot, world) {
+                Some(value) => interp.write_input(value),
+                None => return Ok(()),
+            },
+            Action::Halt => return Ok(()),
+        }
+    }
+}
+
+#[test]
+pub fn painting_test() {
+    // The example moves from 2019 day 11, scripted rather than computed
+    let program = super::assembler::assemble(
+        "
+                IN -> [t]
+                OUT #1
+                OUT #0
+                IN -> [t]
+                OUT #0
+                OUT #0
+                IN -> [t]
+                OUT #1
+                OUT #0
+                IN -> [t]
+                OUT #1
+                OUT #0
+                IN -> [t]
+                OUT #0
+                OUT #1
+                IN -> [t]
+                OUT #1
+                OUT #0
+                IN -> [t]
+                OUT #1
+                OUT #0
+                HALT
+        t:      DATA 0
+        ",
+    )
+    .unwrap()
+    .words;
+
+    let mut robot = Robot::default();
+    let mut world = World::default();
+    let mut interp = IntCodeInterpreter::new(&program);
+    run_robot(&mut interp, &mut PaintingProtocol, &mut robot, &mut world).unwrap();
+
+    assert_eq!(world.painted.len(), 6);
+    assert_eq!(robot.pos, Pos::new(-1, 0));
+    assert_eq!(robot.facing, Direction::Left);
+    assert_eq!(
+        world
+            .painted
+            .render(|c| if c == Some(&1) { '#' } else { '.' }),
+        "..#\n..#\n##."
+    );
+
+    let mut interp = IntCodeInterpreter::new(&vec![104, 1, 104, 7, 99]);
+    assert_eq!(
+        run_robot(
+            &mut interp,
+            &mut PaintingProtocol,
+            &mut Robot::default(),
+            &mut World::default()
+        ),
+        Err(RobotError::BadOutput(7))
+    );
+}
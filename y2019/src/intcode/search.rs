@@ -0,0 +1,606 @@
+//! Finds values for some of a program's memory cells that make it produce a
+//! wanted result, as in 2019 day 2 part 2.
+//!
+//! The program is first run symbolically, with the unknown cells standing
+//! for variables, to get an expression for the result, which can usually be
+//! solved directly. Programs whose control flow or addressing depends on the
+//! unknowns can't be handled that way, so those fall back to trying every
+//! combination of values, spread over as many threads as there are cores.
+
+use super::limits::{Limits, Outcome};
+use super::memory::FLAT_LIMIT;
+use super::{Action, Instruction, InstructionByte, IntCodeInterpreter, MemoryData};
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::thread;
+
+/// How many instructions a single run may take before it's abandoned.
+pub const STEP_LIMIT: usize = 1_000_000;
+
+/// A memory cell whose value is to be found, and the values it may take.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unknown {
+    pub address: usize,
+    pub values: RangeInclusive<InstructionByte>,
+}
+
+/// Which of the program's results has to match the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// The given memory cell once the program halts.
+    Memory(usize),
+    /// The nth output, counting from zero.
+    Output(usize),
+}
+
+/// A result in terms of the unknowns, where `Var(i)` is the ith unknown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Const(InstructionByte),
+    Var(usize),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+}
+
+impl Expr {
+    /// The value with the unknowns set to `values`, or `None` on overflow.
+    pub fn eval(&self, values: &[InstructionByte]) -> Option<InstructionByte> {
+        match self {
+            Expr::Const(value) => Some(*value),
+            Expr::Var(i) => Some(values[*i]),
+            Expr::Add(lhs, rhs) => lhs.eval(values)?.checked_add(rhs.eval(values)?),
+            Expr::Mul(lhs, rhs) => lhs.eval(values)?.checked_mul(rhs.eval(values)?),
+        }
+    }
+
+    fn constant(&self) -> Option<InstructionByte> {
+        match self {
+            Expr::Const(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Const(value) => write!(f, "{}", value),
+            Expr::Var(i) => write!(f, "x{}", i),
+            Expr::Add(lhs, rhs) => write!(f, "({} + {})", lhs, rhs),
+            Expr::Mul(lhs, rhs) => write!(f, "({} * {})", lhs, rhs),
+        }
+    }
+}
+
+/// Why a program couldn't be run symbolically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unsupported {
+    /// The instruction at this address, or a jump, comparison, write address
+    /// or relative base adjustment it makes, depends on the unknowns.
+    DependsOnUnknowns(usize),
+    /// The goal depends on the unknowns through a read from an address that
+    /// itself depends on them.
+    Opaque,
+    /// The program failed, ran out of input or ran for more than
+    /// `STEP_LIMIT` instructions before reaching the goal.
+    NotReached,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unsupported::DependsOnUnknowns(ip) => {
+                write!(f, "instruction at {} depends on the unknowns", ip)
+            }
+            Unsupported::Opaque => write!(f, "goal read through an unknown address"),
+            Unsupported::NotReached => write!(f, "goal not reached"),
+        }
+    }
+}
+
+/// A question of the form "which values of these cells make the program
+/// produce this result?"
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub program: MemoryData,
+    pub unknowns: Vec<Unknown>,
+    /// Input queued before the program starts.
+    pub inputs: Vec<InstructionByte>,
+    pub goal: Goal,
+}
+
+impl Search {
+    pub fn new(program: &MemoryData, unknowns: Vec<Unknown>, goal: Goal) -> Self {
+        Self {
+            program: program.clone(),
+            unknowns,
+            inputs: Vec::new(),
+            goal,
+        }
+    }
+
+    /// Runs the program symbolically, returning the goal in terms of the
+    /// unknowns.
+    pub fn expression(&self) -> Result<Expr, Unsupported> {
+        let mut machine = Symbolic {
+            memory: self
+                .program
+                .iter()
+                .map(|word| Some(constant(*word)))
+                .collect(),
+            ip: 0,
+            relative_base: 0,
+            inputs: self.inputs.iter().copied(),
+            outputs: Vec::new(),
+        };
+        for (i, unknown) in self.unknowns.iter().enumerate() {
+            machine.store(unknown.address, Some(Rc::new(Expr::Var(i))))?;
+        }
+
+        let cell = machine.run(self.goal)?;
+        cell.map(|expr| (*expr).clone()).ok_or(Unsupported::Opaque)
+    }
+
+    /// Runs the program for real with the unknowns set to `values`, giving
+    /// the goal's value, or `None` if it's never reached. Unknowns beyond
+    /// flat memory's limit are never reached either.
+    pub fn evaluate(&self, values: &[InstructionByte]) -> Option<InstructionByte> {
+        let mut memory = self.program.clone();
+        for (unknown, value) in self.unknowns.iter().zip(values) {
+            if unknown.address >= FLAT_LIMIT {
+                return None;
+            }
+            if unknown.address >= memory.len() {
+                memory.resize(unknown.address + 1, 0);
+            }
+            memory[unknown.address] = *value;
+        }
+
+        let mut interp = IntCodeInterpreter::new(&memory);
+        for input in &self.inputs {
+            interp.write_input(*input);
+        }
+
+        let mut limits = Limits::new().with_instructions(STEP_LIMIT as u64);
+        let mut outputs = 0;
+        loop {
+            match interp.run_limited(&mut limits).ok()? {
+                Outcome::Action(Action::Output(value)) => {
+                    if self.goal == Goal::Output(outputs) {
+                        return Some(value);
+                    }
+                    outputs += 1;
+                }
+                Outcome::Action(Action::Halt) => {
+                    return match self.goal {
+                        Goal::Memory(address) => Some(interp.memory.get(address)),
+                        Goal::Output(_) => None,
+                    }
+                }
+                Outcome::Action(Action::NeedInput) | Outcome::Stopped(_) => return None,
+            }
+        }
+    }
+
+    /// The first values for the unknowns, taken in order, that make the goal
+    /// equal `target`. Solves the goal's expression if there is one, and
+    /// otherwise tries every combination.
+    pub fn solve(&self, target: InstructionByte) -> Option<Vec<InstructionByte>> {
+        match self.expression() {
+            Ok(expr) => self.solve_expression(&expr, target),
+            Err(_) => self.brute_force(target),
+        }
+    }
+
+    /// Like `solve`, but always by running the program for every
+    /// combination of values.
+    pub fn brute_force(&self, target: InstructionByte) -> Option<Vec<InstructionByte>> {
+        let (first, rest) = match self.unknowns.split_first() {
+            Some(split) => split,
+            None => return self.check(Vec::new(), target),
+        };
+
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let best: Mutex<Option<Vec<InstructionByte>>> = Mutex::new(None);
+
+        // Each thread takes every nth value of the first unknown and stops
+        // once another thread has found an answer that comes earlier
+        thread::scope(|scope| {
+            for thread in 0..threads {
+                let best = &best;
+                scope.spawn(move || {
+                    for value in first.values.clone().skip(thread).step_by(threads) {
+                        if matches!(&*best.lock().unwrap(), Some(found) if found[0] < value) {
+                            return;
+                        }
+                        let found = combinations(rest).find_map(|mut values| {
+                            values.insert(0, value);
+                            self.check(values, target)
+                        });
+                        if let Some(found) = found {
+                            let mut best = best.lock().unwrap();
+                            if best.as_ref().is_none_or(|b| found < *b) {
+                                *best = Some(found);
+                            }
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        best.into_inner().unwrap()
+    }
+
+    fn solve_expression(
+        &self,
+        expr: &Expr,
+        target: InstructionByte,
+    ) -> Option<Vec<InstructionByte>> {
+        let (last, rest) = match self.unknowns.split_last() {
+            Some(split) => split,
+            None => return self.check(Vec::new(), target),
+        };
+        let linear = Linear::new(expr, self.unknowns.len());
+
+        // The expression picks out candidates cheaply, but each is confirmed
+        // by a real run in case some values make the program fail
+        combinations(rest).find_map(|values| {
+            let roots = linear
+                .as_ref()
+                .and_then(|linear| linear.solve_last(&values, target));
+            let candidates: Box<dyn Iterator<Item = InstructionByte>> = match roots {
+                Some(Roots::Nothing) => Box::new(std::iter::empty()),
+                Some(Roots::One(value)) if last.values.contains(&value) => {
+                    Box::new(std::iter::once(value))
+                }
+                Some(Roots::One(_)) => Box::new(std::iter::empty()),
+                Some(Roots::All) | None => Box::new(last.values.clone()),
+            };
+
+            candidates
+                .map(|value| {
+                    let mut values = values.clone();
+                    values.push(value);
+                    values
+                })
+                .filter(|values| expr.eval(values) == Some(target))
+                .find_map(|values| self.check(values, target))
+        })
+    }
+
+    fn check(
+        &self,
+        values: Vec<InstructionByte>,
+        target: InstructionByte,
+    ) -> Option<Vec<InstructionByte>> {
+        if self.evaluate(&values) == Some(target) {
+            Some(values)
+        } else {
+            None
+        }
+    }
+}
+
+/// Every combination of values for `unknowns`, in order.
+fn combinations(unknowns: &[Unknown]) -> impl Iterator<Item = Vec<InstructionByte>> + '_ {
+    let first = if unknowns.iter().any(|unknown| unknown.values.is_empty()) {
+        None
+    } else {
+        Some(
+            unknowns
+                .iter()
+                .map(|unknown| *unknown.values.start())
+                .collect(),
+        )
+    };
+
+    std::iter::successors(first, move |values: &Vec<InstructionByte>| {
+        let mut next = values.clone();
+        for i in (0..unknowns.len()).rev() {
+            if next[i] < *unknowns[i].values.end() {
+                next[i] += 1;
+                return Some(next);
+            }
+            next[i] = *unknowns[i].values.start();
+        }
+        None
+    })
+}
+
+/// `constant + coefficients[0] * x0 + coefficients[1] * x1 + ...`
+struct Linear {
+    constant: InstructionByte,
+    coefficients: Vec<InstructionByte>,
+}
+
+enum Roots {
+    Nothing,
+    One(InstructionByte),
+    All,
+}
+
+impl Linear {
+    /// The expression in linear form, or `None` if it isn't linear or the
+    /// coefficients overflow.
+    fn new(expr: &Expr, unknowns: usize) -> Option<Linear> {
+        match expr {
+            Expr::Const(value) => Some(Linear {
+                constant: *value,
+                coefficients: vec![0; unknowns],
+            }),
+            Expr::Var(i) => {
+                let mut coefficients = vec![0; unknowns];
+                coefficients[*i] = 1;
+                Some(Linear {
+                    constant: 0,
+                    coefficients,
+                })
+            }
+            Expr::Add(lhs, rhs) => {
+                let lhs = Linear::new(lhs, unknowns)?;
+                let rhs = Linear::new(rhs, unknowns)?;
+                Some(Linear {
+                    constant: lhs.constant.checked_add(rhs.constant)?,
+                    coefficients: lhs
+                        .coefficients
+                        .iter()
+                        .zip(&rhs.coefficients)
+                        .map(|(a, b)| a.checked_add(*b))
+                        .collect::<Option<_>>()?,
+                })
+            }
+            Expr::Mul(lhs, rhs) => {
+                let lhs = Linear::new(lhs, unknowns)?;
+                let rhs = Linear::new(rhs, unknowns)?;
+                let (scale, linear) = if lhs.is_constant() {
+                    (lhs.constant, rhs)
+                } else if rhs.is_constant() {
+                    (rhs.constant, lhs)
+                } else {
+                    return None;
+                };
+                Some(Linear {
+                    constant: linear.constant.checked_mul(scale)?,
+                    coefficients: linear
+                        .coefficients
+                        .iter()
+                        .map(|a| a.checked_mul(scale))
+                        .collect::<Option<_>>()?,
+                })
+            }
+        }
+    }
+
+    fn is_constant(&self) -> bool {
+        self.coefficients.iter().all(|a| *a == 0)
+    }
+
+    /// The values of the last unknown that make the expression equal
+    /// `target` when the others are `values`, or `None` on overflow.
+    fn solve_last(&self, values: &[InstructionByte], target: InstructionByte) -> Option<Roots> {
+        let mut rest = target.checked_sub(self.constant)?;
+        for (a, value) in self.coefficients.iter().zip(values) {
+            rest = rest.checked_sub(a.checked_mul(*value)?)?;
+        }
+
+        let a = *self.coefficients.last()?;
+        Some(if a == 0 {
+            if rest == 0 {
+                Roots::All
+            } else {
+                Roots::Nothing
+            }
+        } else if rest % a == 0 {
+            Roots::One(rest / a)
+        } else {
+            Roots::Nothing
+        })
+    }
+}
+
+/// A memory cell during symbolic execution. `None` is a value that depends
+/// on the unknowns in a way an `Expr` can't describe.
+type Cell = Option<Rc<Expr>>;
+
+fn constant(value: InstructionByte) -> Rc<Expr> {
+    Rc::new(Expr::Const(value))
+}
+
+/// Mirrors `IntCodeInterpreter`, with flat memory, but over expressions.
+struct Symbolic<I> {
+    memory: Vec<Cell>,
+    ip: usize,
+    relative_base: InstructionByte,
+    inputs: I,
+    outputs: Vec<Cell>,
+}
+
+impl<I: Iterator<Item = InstructionByte>> Symbolic<I> {
+    fn read(&self, address: usize) -> Cell {
+        match self.memory.get(address) {
+            Some(cell) => cell.clone(),
+            None => Some(constant(0)),
+        }
+    }
+
+    fn store(&mut self, address: usize, cell: Cell) -> Result<(), Unsupported> {
+        if address >= FLAT_LIMIT {
+            return Err(Unsupported::NotReached);
+        }
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, Some(constant(0)));
+        }
+        self.memory[address] = cell;
+        Ok(())
+    }
+
+    fn concrete(&self, cell: &Cell) -> Result<InstructionByte, Unsupported> {
+        cell.as_ref()
+            .and_then(|expr| expr.constant())
+            .ok_or(Unsupported::DependsOnUnknowns(self.ip))
+    }
+
+    /// Where parameter `n` of the current instruction points, given its mode.
+    fn address(&self, n: usize, mode: InstructionByte) -> Result<Option<usize>, Unsupported> {
+        let param = match self.read(self.ip + n) {
+            Some(expr) => match expr.constant() {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        let address = match mode {
+            0 => param,
+            _ => self
+                .relative_base
+                .checked_add(param)
+                .ok_or(Unsupported::NotReached)?,
+        };
+        usize::try_from(address)
+            .map(Some)
+            .map_err(|_| Unsupported::NotReached)
+    }
+
+    fn load(&self, n: usize, mode: InstructionByte) -> Result<Cell, Unsupported> {
+        if mode == 1 {
+            return Ok(self.read(self.ip + n));
+        }
+        Ok(match self.address(n, mode)? {
+            Some(address) => self.read(address),
+            None => None,
+        })
+    }
+
+    fn save(&mut self, n: usize, mode: InstructionByte, cell: Cell) -> Result<(), Unsupported> {
+        if mode == 1 {
+            return Err(Unsupported::NotReached);
+        }
+        match self.address(n, mode)? {
+            Some(address) => self.store(address, cell),
+            None => Err(Unsupported::DependsOnUnknowns(self.ip)),
+        }
+    }
+
+    fn run(&mut self, goal: Goal) -> Result<Cell, Unsupported> {
+        for _ in 0..STEP_LIMIT {
+            let word = self.concrete(&self.read(self.ip))?;
+            let (inst, size) =
+                Instruction::decode(&[word][..], 0).map_err(|_| Unsupported::NotReached)?;
+            let mode = |n: u32| (word / (10 as InstructionByte).pow(n + 1)) % 10;
+            let mut next = self.ip + size;
+
+            match inst {
+                Instruction::Add(..) | Instruction::Multiply(..) => {
+                    let lhs = self.load(1, mode(1))?;
+                    let rhs = self.load(2, mode(2))?;
+                    let result = match (lhs, rhs) {
+                        (Some(lhs), Some(rhs)) => match (lhs.constant(), rhs.constant()) {
+                            (Some(a), Some(b)) => {
+                                let value = match inst {
+                                    Instruction::Add(..) => a.checked_add(b),
+                                    _ => a.checked_mul(b),
+                                };
+                                Some(constant(value.ok_or(Unsupported::NotReached)?))
+                            }
+                            _ => Some(Rc::new(match inst {
+                                Instruction::Add(..) => Expr::Add(lhs, rhs),
+                                _ => Expr::Mul(lhs, rhs),
+                            })),
+                        },
+                        _ => None,
+                    };
+                    self.save(3, mode(3), result)?;
+                }
+                Instruction::Input(_) => {
+                    let value = self.inputs.next().ok_or(Unsupported::NotReached)?;
+                    self.save(1, mode(1), Some(constant(value)))?;
+                }
+                Instruction::Output(_) => {
+                    let cell = self.load(1, mode(1))?;
+                    self.outputs.push(cell);
+                    if goal == Goal::Output(self.outputs.len() - 1) {
+                        return Ok(self.outputs.pop().unwrap());
+                    }
+                }
+                Instruction::JumpIfTrue(..) | Instruction::JumpIfFalse(..) => {
+                    let value = self.concrete(&self.load(1, mode(1))?)?;
+                    let target = self.concrete(&self.load(2, mode(2))?)?;
+                    let jump = match inst {
+                        Instruction::JumpIfTrue(..) => value != 0,
+                        _ => value == 0,
+                    };
+                    if jump {
+                        next = match usize::try_from(target) {
+                            Ok(target) if target < self.memory.len() => target,
+                            _ => return Err(Unsupported::NotReached),
+                        };
+                    }
+                }
+                Instruction::LessThan(..) | Instruction::Equals(..) => {
+                    let a = self.concrete(&self.load(1, mode(1))?)?;
+                    let b = self.concrete(&self.load(2, mode(2))?)?;
+                    let result = match inst {
+                        Instruction::LessThan(..) => a < b,
+                        _ => a == b,
+                    };
+                    self.save(3, mode(3), Some(constant(result as InstructionByte)))?;
+                }
+                Instruction::AdjustRelativeBase(_) => {
+                    let value = self.concrete(&self.load(1, mode(1))?)?;
+                    self.relative_base = self
+                        .relative_base
+                        .checked_add(value)
+                        .ok_or(Unsupported::NotReached)?;
+                }
+                Instruction::Halt => {
+                    return match goal {
+                        Goal::Memory(address) => Ok(self.read(address)),
+                        Goal::Output(_) => Err(Unsupported::NotReached),
+                    }
+                }
+            }
+
+            self.ip = next;
+        }
+        Err(Unsupported::NotReached)
+    }
+}
+
+#[test]
+pub fn search_test() {
+    // The example from 2019 day 2, with some of its data made unknown
+    let program = vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50];
+    let unknown = |address| Unknown {
+        address,
+        values: 0..=100,
+    };
+
+    let search = Search::new(&program, vec![unknown(9), unknown(10)], Goal::Memory(0));
+    let expr = search.expression().unwrap();
+    assert_eq!(expr.to_string(), "((x0 + x1) * 50)");
+    assert_eq!(expr.eval(&[30, 40]), Some(3500));
+    assert_eq!(search.solve(3500), Some(vec![0, 70]));
+    assert_eq!(search.brute_force(3500), Some(vec![0, 70]));
+    assert_eq!(search.solve(3501), None);
+
+    // Not linear, so solved by evaluating the expression instead
+    let search = Search::new(&program, vec![unknown(9), unknown(11)], Goal::Memory(0));
+    assert_eq!(search.solve(3500), Some(vec![10, 70]));
+    assert_eq!(search.brute_force(3500), Some(vec![10, 70]));
+
+    // The output depends on a comparison with the unknown
+    let program = vec![1008, 9, 5, 10, 4, 10, 99, 0, 0, 0, 0];
+    let search = Search::new(&program, vec![unknown(9)], Goal::Output(0));
+    assert_eq!(search.expression(), Err(Unsupported::DependsOnUnknowns(0)));
+    assert_eq!(search.solve(1), Some(vec![5]));
+    assert_eq!(search.solve(2), None);
+
+    // Unknowns too far out to hold aren't allocated for
+    let search = Search::new(&program, vec![unknown(1 << 40)], Goal::Output(0));
+    assert_eq!(search.evaluate(&[1]), None);
+    assert_eq!(search.solve(0), None);
+}
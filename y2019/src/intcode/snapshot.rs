@@ -0,0 +1,266 @@
+use super::memory::{Memory, MemoryKind, FLAT_LIMIT};
+use super::{InstructionByte, IntCodeInterpreter, MemoryData};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+const HEADER: &str = "intcode-snapshot 2";
+
+/// The complete state of a machine, which can be written to and read back
+/// from a small text format:
+///
+/// ```text
+/// intcode-snapshot 2
+/// ip 4
+/// relative_base 0
+/// inputs 7,8
+/// memory paged 20000001
+/// run 0 3,0,4,0,99
+/// run 20000000 2
+/// ```
+///
+/// Memory is kept as runs of non-zero words, so a machine that has written
+/// far out into paged memory still snapshots small.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub kind: MemoryKind,
+    pub len: usize,
+    /// `(start address, words)` runs; everything else up to `len` is zero.
+    pub memory: Vec<(usize, MemoryData)>,
+    pub ip: usize,
+    pub relative_base: InstructionByte,
+    pub inputs: MemoryData,
+}
+
+impl IntCodeInterpreter {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            kind: self.memory.kind(),
+            len: self.memory.len(),
+            memory: self.memory.runs(),
+            ip: self.ip,
+            relative_base: self.relative_base,
+            inputs: self.inputs.clone(),
+        }
+    }
+
+    pub fn restore(snapshot: &Snapshot) -> Self {
+        let mut interp = IntCodeInterpreter::with_memory_kind(&Vec::new(), snapshot.kind);
+        interp.memory = Memory::from_runs(snapshot.kind, snapshot.len, &snapshot.memory);
+        interp.ip = snapshot.ip;
+        interp.relative_base = snapshot.relative_base;
+        interp.inputs = snapshot.inputs.clone();
+        interp
+    }
+}
+
+fn join(words: &[InstructionByte]) -> String {
+    words
+        .iter()
+        .map(|w| w.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "ip {}", self.ip)?;
+        writeln!(f, "relative_base {}", self.relative_base)?;
+        writeln!(f, "inputs {}", join(&self.inputs))?;
+        let kind = match self.kind {
+            MemoryKind::Flat => "flat",
+            MemoryKind::Paged => "paged",
+        };
+        writeln!(f, "memory {} {}", kind, self.len)?;
+        for (start, words) in &self.memory {
+            writeln!(f, "run {} {}", start, join(words))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Snapshot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err(format!("not an {} file", HEADER));
+        }
+
+        let mut field = |name: &str| {
+            let line = lines.next().ok_or(format!("missing {}", name))?;
+            if line == name {
+                return Ok("");
+            }
+            line.strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix(' '))
+                .ok_or(format!("expected {}, found '{}'", name, line))
+        };
+        fn words(value: &str) -> Result<MemoryData, String> {
+            value
+                .split(',')
+                .filter(|w| !w.is_empty())
+                .map(|w| w.parse().map_err(|_| format!("bad word '{}'", w)))
+                .collect()
+        }
+
+        let ip = field("ip")?;
+        let ip = ip.parse().map_err(|_| format!("bad ip '{}'", ip))?;
+        let relative_base = field("relative_base")?;
+        let relative_base = relative_base
+            .parse()
+            .map_err(|_| format!("bad relative_base '{}'", relative_base))?;
+        let inputs = words(field("inputs")?)?;
+        let memory = field("memory")?;
+
+        let mut parts = memory.split(' ');
+        let kind = match parts.next() {
+            Some("flat") => MemoryKind::Flat,
+            Some("paged") => MemoryKind::Paged,
+            _ => return Err(format!("bad memory '{}'", memory)),
+        };
+        let len: usize = parts
+            .next()
+            .and_then(|len| len.parse().ok())
+            .ok_or(format!("bad memory '{}'", memory))?;
+        if kind == MemoryKind::Flat && len > FLAT_LIMIT {
+            return Err(format!("flat memory of {} words is too large", len));
+        }
+
+        let mut runs = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let run = line
+                .strip_prefix("run ")
+                .ok_or(format!("expected run, found '{}'", line))?;
+            let (start, run_words) = run.split_once(' ').unwrap_or((run, ""));
+            let start: usize = start
+                .parse()
+                .map_err(|_| format!("bad run start '{}'", start))?;
+            let run_words = words(run_words)?;
+            if start
+                .checked_add(run_words.len())
+                .is_none_or(|end| end > len)
+            {
+                return Err(format!("run at {} goes past the end of memory", start));
+            }
+            runs.push((start, run_words));
+        }
+
+        Ok(Snapshot {
+            kind,
+            len,
+            memory: runs,
+            ip,
+            relative_base,
+            inputs,
+        })
+    }
+}
+
+impl Snapshot {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[test]
+pub fn snapshot_test() {
+    use super::Action;
+
+    // Outputs double each input, forever
+    let program = vec![3, 9, 1002, 9, 2, 9, 4, 9, 1105, 1, 0];
+    let mut prog = IntCodeInterpreter::new(&program);
+    prog.write_input(5);
+    assert_eq!(prog.run().unwrap(), Action::Output(10));
+    prog.write_input(6);
+    prog.write_input(7);
+
+    // Branch the machine in memory, and via disk
+    let mut branch = prog.clone();
+    let path = std::env::temp_dir().join(format!("intcode-snapshot-{}.txt", std::process::id()));
+    prog.snapshot().save(&path).unwrap();
+    let loaded = Snapshot::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, prog.snapshot());
+    let mut restored = IntCodeInterpreter::restore(&loaded);
+
+    for machine in [&mut prog, &mut branch, &mut restored].iter_mut() {
+        assert_eq!(machine.run().unwrap(), Action::Output(12));
+        assert_eq!(machine.run().unwrap(), Action::Output(14));
+        assert_eq!(machine.run().unwrap(), Action::NeedInput);
+    }
+
+    assert!(
+        "intcode-snapshot 2\nip 0\nrelative_base x\ninputs\nmemory flat 1\nrun 0 99"
+            .parse::<Snapshot>()
+            .is_err()
+    );
+    assert_eq!(
+        "intcode-snapshot 2\nip 2\nrelative_base -3\ninputs\nmemory flat 1\nrun 0 99"
+            .parse::<Snapshot>()
+            .unwrap()
+            .relative_base,
+        -3
+    );
+    assert!(
+        "intcode-snapshot 2\nip 0\nrelative_base 0\ninputs\nmemory huge 1\nrun 0 99"
+            .parse::<Snapshot>()
+            .is_err()
+    );
+
+    // Memory that can't be rebuilt is refused rather than allocated
+    for memory in [
+        "memory flat 100000000000000",
+        "memory flat 4\nrun 2 1,2,3",
+        "memory paged 100\nrun 18446744073709551615 1,2",
+        "memory paged 100\nrun 1000 1",
+    ]
+    .iter()
+    {
+        let text = format!(
+            "intcode-snapshot 2\nip 0\nrelative_base 0\ninputs\n{}",
+            memory
+        );
+        assert!(text.parse::<Snapshot>().is_err(), "{}", memory);
+    }
+}
+
+#[test]
+pub fn paged_snapshot_test() {
+    use super::Action;
+
+    // ADD #1, #1 -> [20000000]; IN -> [20000001]; OUT [20000001]; HALT
+    let program = vec![1101, 1, 1, 20000000, 3, 20000001, 4, 20000001, 99];
+    let mut prog = IntCodeInterpreter::with_memory_kind(&program, MemoryKind::Paged);
+    assert_eq!(prog.run().unwrap(), Action::NeedInput);
+
+    let snapshot = prog.snapshot();
+    assert_eq!(snapshot.kind, MemoryKind::Paged);
+    assert_eq!(snapshot.len, 20000001);
+    assert_eq!(
+        snapshot.memory,
+        vec![(0, program.clone()), (20000000, vec![2])]
+    );
+    assert!(snapshot.to_string().len() < 200);
+
+    let parsed = snapshot.to_string().parse::<Snapshot>().unwrap();
+    assert_eq!(parsed, snapshot);
+    let mut restored = IntCodeInterpreter::restore(&parsed);
+
+    for machine in [&mut prog, &mut restored].iter_mut() {
+        machine.write_input(7);
+        assert_eq!(machine.run().unwrap(), Action::Output(7));
+        assert_eq!(machine.run().unwrap(), Action::Halt);
+        assert_eq!(machine.memory.get(20000000), 2);
+    }
+}
@@ -0,0 +1,124 @@
+use super::{Action, InstructionByte, IntCodeInterpreter, IntcodeError, IntcodeErrorKind};
+
+/// The values a program outputs, computed lazily as the iterator is
+/// advanced. Created by `IntCodeInterpreter::outputs`.
+pub struct Outputs<'a, I> {
+    interp: &'a mut IntCodeInterpreter,
+    inputs: I,
+    done: bool,
+}
+
+impl<'a, I: Iterator<Item = InstructionByte>> Iterator for Outputs<'a, I> {
+    type Item = Result<InstructionByte, IntcodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.interp.run() {
+                Ok(Action::Output(value)) => return Some(Ok(value)),
+                Ok(Action::NeedInput) => match self.inputs.next() {
+                    Some(value) => self.interp.write_input(value),
+                    None => {
+                        self.done = true;
+                        return Some(Err(IntcodeError {
+                            ip: self.interp.ip,
+                            instruction: self.interp.memory.get(self.interp.ip),
+                            kind: IntcodeErrorKind::InputExhausted,
+                        }));
+                    }
+                },
+                Ok(Action::Halt) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl IntCodeInterpreter {
+    /// Runs the program, taking values from `inputs` whenever it needs
+    /// input, and yields each value it outputs. The iterator ends when the
+    /// program halts, or after yielding an error, including
+    /// `InputExhausted` if `inputs` runs out.
+    pub fn outputs<I>(&mut self, inputs: I) -> Outputs<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = InstructionByte>,
+    {
+        Outputs {
+            interp: self,
+            inputs: inputs.into_iter(),
+            done: false,
+        }
+    }
+
+    /// Runs the program until it halts, returning everything it output.
+    pub fn run_to_completion<I>(&mut self, inputs: I) -> Result<Vec<InstructionByte>, IntcodeError>
+    where
+        I: IntoIterator<Item = InstructionByte>,
+    {
+        self.outputs(inputs).collect()
+    }
+
+    /// Runs the program until it halts, returning the last value it output,
+    /// or `NoOutput` if there wasn't one.
+    pub fn last_output<I>(&mut self, inputs: I) -> Result<InstructionByte, IntcodeError>
+    where
+        I: IntoIterator<Item = InstructionByte>,
+    {
+        let mut last = None;
+        for output in self.outputs(inputs) {
+            last = Some(output?);
+        }
+        last.ok_or_else(|| IntcodeError {
+            ip: self.ip,
+            instruction: self.memory.get(self.ip),
+            kind: IntcodeErrorKind::NoOutput,
+        })
+    }
+}
+
+#[test]
+pub fn stream_test() {
+    // Outputs double each input until it reads a zero
+    let program = super::assembler::assemble(
+        "
+        loop:   IN -> [x]
+                JZ [x], #end
+                MUL [x], #2 -> [x]
+                OUT [x]
+                JZ #0, #loop
+        end:    HALT
+        x:      DATA 0
+        ",
+    )
+    .unwrap()
+    .words;
+
+    let mut prog = IntCodeInterpreter::new(&program);
+    assert_eq!(prog.run_to_completion(vec![1, 2, 3, 0]), Ok(vec![2, 4, 6]));
+
+    let mut prog = IntCodeInterpreter::new(&program);
+    let mut outputs = prog.outputs(1..);
+    assert_eq!(outputs.next(), Some(Ok(2)));
+    assert_eq!(outputs.next(), Some(Ok(4)));
+
+    let mut prog = IntCodeInterpreter::new(&program);
+    let err = prog.run_to_completion(vec![5]).unwrap_err();
+    assert_eq!(err.kind, IntcodeErrorKind::InputExhausted);
+    assert_eq!(err.ip, 0);
+
+    let mut prog = IntCodeInterpreter::new(&program);
+    let mut outputs = prog.outputs(vec![]);
+    assert!(outputs.next().unwrap().is_err());
+    assert_eq!(outputs.next(), None);
+
+    let mut prog = IntCodeInterpreter::new(&program);
+    assert_eq!(prog.last_output(vec![1, 2, 3, 0]), Ok(6));
+    let mut prog = IntCodeInterpreter::new(&program);
+    assert_eq!(
+        prog.last_output(vec![0]).unwrap_err().kind,
+        IntcodeErrorKind::NoOutput
+    );
+}
@@ -0,0 +1,109 @@
+use super::{Instruction, InstructionByte};
+use std::fmt;
+
+/// Something observed while executing, recorded when tracing is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    Instruction {
+        ip: usize,
+        instruction: Instruction,
+    },
+    /// An operand's resolved value; `address` is `None` for immediates.
+    Read {
+        address: Option<usize>,
+        value: InstructionByte,
+    },
+    Write {
+        address: usize,
+        old: InstructionByte,
+        new: InstructionByte,
+    },
+    RelativeBase {
+        old: InstructionByte,
+        new: InstructionByte,
+    },
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceEvent::Instruction { ip, instruction } => write!(f, "{:04}: {}", ip, instruction),
+            TraceEvent::Read {
+                address: Some(address),
+                value,
+            } => write!(f, "      read [{}] = {}", address, value),
+            TraceEvent::Read {
+                address: None,
+                value,
+            } => write!(f, "      read #{}", value),
+            TraceEvent::Write { address, old, new } => {
+                write!(f, "      write [{}] {} -> {}", address, old, new)
+            }
+            TraceEvent::RelativeBase { old, new } => write!(f, "      rb {} -> {}", old, new),
+        }
+    }
+}
+
+#[test]
+pub fn trace_test() {
+    use super::{IntCodeInterpreter, Parameter};
+
+    let mut prog = IntCodeInterpreter::new(&vec![109, 5, 1201, 0, 7, 1, 99, 3]);
+    prog.set_tracing(true);
+    prog.run().unwrap();
+
+    let trace = prog.take_trace();
+    assert_eq!(
+        trace[0],
+        TraceEvent::Instruction {
+            ip: 0,
+            instruction: Instruction::AdjustRelativeBase(Parameter::Immediate(5))
+        }
+    );
+    assert_eq!(trace[2], TraceEvent::RelativeBase { old: 0, new: 5 });
+    assert_eq!(
+        trace[3..6],
+        [
+            TraceEvent::Instruction {
+                ip: 2,
+                instruction: Instruction::Add(
+                    Parameter::Relative(0),
+                    Parameter::Immediate(7),
+                    Parameter::Position(1)
+                )
+            },
+            TraceEvent::Read {
+                address: Some(5),
+                value: 1
+            },
+            TraceEvent::Read {
+                address: None,
+                value: 7
+            },
+        ]
+    );
+    assert_eq!(
+        trace[6],
+        TraceEvent::Write {
+            address: 1,
+            old: 5,
+            new: 8
+        }
+    );
+    assert_eq!(trace.len(), 8);
+    assert!(prog.take_trace().is_empty());
+
+    let lines = trace.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(lines[0], "0000: ARB #5");
+    assert_eq!(lines[6], "      write [1] 5 -> 8");
+
+    // An IN waiting for input is only traced when it gets some
+    let mut prog = IntCodeInterpreter::new(&vec![3, 0, 99]);
+    prog.set_tracing(true);
+    assert_eq!(prog.run().unwrap(), super::Action::NeedInput);
+    assert_eq!(prog.run().unwrap(), super::Action::NeedInput);
+    assert!(prog.take_trace().is_empty());
+    prog.write_input(5);
+    prog.run().unwrap();
+    assert_eq!(prog.take_trace().len(), 3);
+}
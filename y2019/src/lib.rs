@@ -0,0 +1,14 @@
+#[macro_use]
+extern crate aoc_runner_derive;
+
+mod day01;
+mod day02;
+mod day05;
+mod day07;
+mod day09;
+mod day11;
+mod day13;
+
+pub mod intcode;
+
+aoc_lib! {year = 2019}
@@ -1,3 +1,8 @@
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate aoc_runner_derive;
+
 mod day01;
 mod day02;
 mod day03;
@@ -22,3 +27,5 @@ mod day21;
 mod day22;
 mod day24;
 mod day25;
+
+aoc_lib! {year = 2020}